@@ -92,24 +92,20 @@ impl Distro {
     );
 
     for (variant, AsciiDistro { pattern, .. }) in &variants {
-        let patterns = pattern.split('|').map(|s| s.trim());
         let mut conds = Vec::new();
 
-        for m in patterns {
-            let stripped = m.trim_matches(['*', '\'', '"']).to_lowercase();
-
-            if stripped.contains(['*', '"']) {
-                if let Some((prefix, suffix)) = stripped.split_once(r#""*""#) {
-                    conds.push(format!(
-                        r#"name.starts_with("{prefix}") && name.ends_with("{suffix}")"#
-                    ));
-                    continue;
-                }
-                todo!("cannot properly parse: {m}");
+        for m in normalize_patterns(pattern) {
+            let stripped = m.trim_matches('*');
+
+            if let Some((prefix, suffix)) = stripped.split_once('*') {
+                conds.push(format!(
+                    r#"name.starts_with("{prefix}") && name.ends_with("{suffix}")"#
+                ));
+                continue;
             }
 
             // Exact matches
-            if m.trim_matches('*') == m {
+            if stripped == m {
                 conds.push(format!(r#"name == "{stripped}""#));
                 continue;
             }
@@ -176,6 +172,67 @@ impl Distro {
         };
         &art[1..art.len().checked_sub(1).unwrap()]
     }
+
+    /// Gets the name of the variant.
+    pub fn name(&self) -> &'static str {
+        match self {
+"###,
+    );
+
+    for variant in variants.keys() {
+        write!(
+            buf,
+            r###"
+            Self::{variant} => "{variant}",
+"###,
+        )
+        .unwrap();
+    }
+
+    buf.push_str(
+        r###"
+        }
+    }
+
+    /// Gets the lowercase patterns that [`Self::detect`] matches against,
+    /// where `*` matches any characters.
+    pub fn patterns(&self) -> &'static [&'static str] {
+        match self {
+"###,
+    );
+
+    for (variant, AsciiDistro { pattern, .. }) in &variants {
+        let patterns = normalize_patterns(pattern)
+            .map(|m| format!("r#\"{m}\"#"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(
+            buf,
+            r###"
+            Self::{variant} => &[{patterns}],
+"###,
+        )
+        .unwrap();
+    }
+
+    buf.push_str(
+        r###"
+        }
+    }
+}
+
+impl strum::VariantArray for Distro {
+    const VARIANTS: &'static [Self] = &[
+"###,
+    );
+
+    for variant in variants.keys() {
+        writeln!(buf, "        Self::{variant},").unwrap();
+    }
+
+    buf.push_str(
+        r###"
+    ];
 }
 "###,
     );
@@ -183,6 +240,16 @@ impl Distro {
     fs::write(out_path.join("distros.rs"), buf).expect("couldn't write distros.rs");
 }
 
+/// Splits a neofetch case pattern into its alternatives, lowercased and with
+/// the shell quotes removed.
+///
+/// E.g. `"Adélie"* | "Adelie"*` becomes `adélie*` and `adelie*`.
+fn normalize_patterns(pattern: &str) -> impl Iterator<Item = String> + '_ {
+    pattern
+        .split('|')
+        .map(|m| m.trim().replace(['"', '\''], "").to_lowercase())
+}
+
 /// Parses ascii distros from neofetch script.
 fn parse_ascii_distros<P>(neofetch_path: P) -> Vec<AsciiDistro>
 where
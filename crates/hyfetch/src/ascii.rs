@@ -1,21 +1,33 @@
 use std::borrow::Cow;
+use std::cmp;
+use std::collections::BTreeSet;
 use std::fmt::Write as _;
 use std::ops::Range;
 
 use aho_corasick::AhoCorasick;
 use anyhow::{Context as _, Result};
 use indexmap::IndexMap;
+use itertools::Itertools as _;
+use palette::Srgb;
 use tracing::debug;
 use unicode_segmentation::UnicodeSegmentation;
 
 use crate::color_util::{
-    color, ForegroundBackground, NeofetchAsciiIndexedColor, ToAnsiString as _,
+    color, ContrastGrayscale as _, ForegroundBackground, NeofetchAsciiIndexedColor,
+    PresetIndexedColor, ToAnsiString as _,
 };
+use crate::distros::Distro;
 use crate::neofetch_util::{
-    ascii_size, ColorAlignment, NEOFETCH_COLORS_AC, NEOFETCH_COLOR_PATTERNS,
+    ascii_foreground, ascii_size, ColorAlignment, NEOFETCH_COLORS_AC, NEOFETCH_COLOR_PATTERNS,
 };
 use crate::presets::ColorProfile;
-use crate::types::{AnsiMode, TerminalTheme};
+use crate::types::{AnsiMode, LogoStyle, TerminalTheme};
+
+/// Approximate height-to-width ratio of a terminal cell.
+const CELL_ASPECT_RATIO: f32 = 2.0;
+
+/// Maps the `(x, y)` coordinates and color slot of a cell to its color.
+type CellColors = Box<dyn Fn(usize, usize, NeofetchAsciiIndexedColor) -> Option<Srgb<u8>>>;
 
 /// Raw ascii art before any processing.
 #[derive(Clone, Debug)]
@@ -28,8 +40,8 @@ pub struct RawAsciiArt {
 #[derive(Clone, Debug)]
 pub struct NormalizedAsciiArt {
     pub lines: Vec<String>,
-    pub w: u8,
-    pub h: u8,
+    pub w: u16,
+    pub h: u16,
     pub fg: Vec<NeofetchAsciiIndexedColor>,
 }
 
@@ -37,11 +49,25 @@ pub struct NormalizedAsciiArt {
 #[derive(Clone, Debug)]
 pub struct RecoloredAsciiArt {
     pub lines: Vec<String>,
-    pub w: u8,
-    pub h: u8,
+    pub w: u16,
+    pub h: u16,
 }
 
 impl RawAsciiArt {
+    /// Gets the built-in ascii art of a distro.
+    pub fn from_distro(distro: &Distro) -> Self {
+        Self {
+            asc: distro.ascii_art().to_owned(),
+            fg: ascii_foreground(distro),
+        }
+    }
+
+    /// Gets the color slots (`${c1}` to `${c6}`) used in the ascii art, in
+    /// ascending order.
+    pub fn color_slots(&self) -> Vec<NeofetchAsciiIndexedColor> {
+        color_slots(self.asc.lines())
+    }
+
     /// Makes sure every line is the same width.
     #[tracing::instrument(level = "debug", skip(self))]
     pub fn to_normalized(&self) -> Result<NormalizedAsciiArt> {
@@ -69,12 +95,19 @@ impl RawAsciiArt {
 }
 
 impl NormalizedAsciiArt {
+    /// Gets the color slots (`${c1}` to `${c6}`) used in the ascii art, in
+    /// ascending order.
+    pub fn color_slots(&self) -> Vec<NeofetchAsciiIndexedColor> {
+        color_slots(self.lines.iter().map(String::as_str))
+    }
+
     /// Uses a color alignment to recolor the ascii art.
     #[tracing::instrument(level = "debug", skip(self), fields(self.w = self.w, self.h = self.h))]
     pub fn to_recolored(
         &self,
         color_align: &ColorAlignment,
         color_profile: &ColorProfile,
+        style: LogoStyle,
         color_mode: AnsiMode,
         theme: TerminalTheme,
     ) -> Result<RecoloredAsciiArt> {
@@ -88,6 +121,39 @@ impl NormalizedAsciiArt {
             });
         }
 
+        // A custom alignment saved for other ascii art may not fit this one,
+        // either using slots that this one lacks or leaving some of its slots
+        // without a color
+        let color_align = match color_align {
+            ColorAlignment::Custom { colors } => {
+                let slots = self.color_slots();
+                if colors.keys().all(|slot| slots.contains(slot))
+                    && slots.iter().all(|slot| colors.contains_key(slot))
+                {
+                    color_align
+                } else {
+                    debug!(
+                        custom_slots = ?colors.keys().collect::<Vec<_>>(),
+                        ?slots,
+                        "custom color slots don't match the ascii art; falling back to horizontal"
+                    );
+                    &ColorAlignment::Horizontal
+                }
+            },
+            _ => color_align,
+        };
+
+        if style != LogoStyle::Foreground {
+            let lines = self
+                .recolor_cells(color_align, color_profile, style, color_mode, theme)
+                .with_context(|| format!("failed to recolor ascii with style {style:?}"))?;
+            return Ok(RecoloredAsciiArt {
+                lines,
+                w: self.w,
+                h: self.h,
+            });
+        }
+
         let reset = color("&~&*", color_mode).expect("color reset should not be invalid");
 
         let lines = match (color_align, self) {
@@ -121,7 +187,7 @@ impl NormalizedAsciiArt {
 
                 // Add new colors
                 let lines = {
-                    let ColorProfile { colors } = color_profile
+                    let ColorProfile { colors, .. } = color_profile
                         .with_length(self.h.try_into().expect("`h` should not be 0"))
                         .with_context(|| {
                             format!("failed to spread color profile to length {h}", h = self.h)
@@ -182,7 +248,7 @@ impl NormalizedAsciiArt {
 
                         let mut matches = ac.find_iter(line).peekable();
                         let mut dst = String::new();
-                        let mut offset: u8 = 0;
+                        let mut offset: usize = 0;
                         loop {
                             let current = matches.next();
                             let next = matches.peek();
@@ -197,8 +263,7 @@ impl NormalizedAsciiArt {
                                     if offset == 0 && m.start() > 0 {
                                         dst.push_str(&line[..m.start()]);
                                     }
-                                    offset =
-                                        offset.checked_add(u8::try_from(m.len()).unwrap()).unwrap();
+                                    offset = offset.checked_add(m.len()).unwrap();
                                     let mut span = m.span();
                                     span.start = m.end();
                                     span.end = m_next.start();
@@ -215,8 +280,7 @@ impl NormalizedAsciiArt {
                                     if offset == 0 && m.start() > 0 {
                                         dst.push_str(&line[..m.start()]);
                                     }
-                                    offset =
-                                        offset.checked_add(u8::try_from(m.len()).unwrap()).unwrap();
+                                    offset = offset.checked_add(m.len()).unwrap();
                                     let mut span = m.span();
                                     span.start = m.end();
                                     span.end = line.len();
@@ -252,12 +316,12 @@ impl NormalizedAsciiArt {
                                 c_range.start = byte_idx_to_grapheme_idx
                                     .get(&c_range.start)
                                     .unwrap()
-                                    .checked_sub(usize::from(offset))
+                                    .checked_sub(offset)
                                     .unwrap();
                                 c_range.end = byte_idx_to_grapheme_idx
                                     .get(&c_range.end)
                                     .unwrap()
-                                    .checked_sub(usize::from(offset))
+                                    .checked_sub(offset)
                                     .unwrap();
                                 dst.push_str(
                                     &ColorProfile::new(Vec::from(&color_profile.colors[c_range]))
@@ -301,6 +365,47 @@ impl NormalizedAsciiArt {
                     })
                     .collect::<Result<_>>()?
             },
+            (ColorAlignment::Diagonal { .. } | ColorAlignment::Radial { .. }, _) => self
+                .recolor_cells(
+                    color_align,
+                    color_profile,
+                    LogoStyle::Foreground,
+                    color_mode,
+                    theme,
+                )
+                .context("failed to recolor ascii by position")?,
+            (
+                ColorAlignment::Custom {
+                    colors: custom_colors,
+                },
+                _,
+            ) if !color_profile.parts.is_empty() => {
+                let Self { lines, .. } = self
+                    .fill_starting()
+                    .context("failed to fill in starting neofetch color codes")?;
+
+                let slot_colors = self.part_slot_colors(custom_colors, color_profile)?;
+
+                // Apply colors
+                let ac = NEOFETCH_COLORS_AC
+                    .get_or_init(|| AhoCorasick::new(NEOFETCH_COLOR_PATTERNS).unwrap());
+                const N: usize = NEOFETCH_COLOR_PATTERNS.len();
+                lines
+                    .iter()
+                    .enumerate()
+                    .map(|(i, line)| {
+                        let mut replacements = vec![Cow::from(""); N];
+                        for (ai, colors) in &slot_colors {
+                            replacements[usize::from(u8::from(*ai)).checked_sub(1).unwrap()] =
+                                colors[i]
+                                    .to_ansi_string(color_mode, ForegroundBackground::Foreground)
+                                    .into();
+                        }
+                        // Reset colors at end of each line to prevent color bleeding
+                        format!("{line}{reset}", line = ac.replace_all(line, &replacements))
+                    })
+                    .collect()
+            },
             (
                 ColorAlignment::Custom {
                     colors: custom_colors,
@@ -311,7 +416,7 @@ impl NormalizedAsciiArt {
                     .fill_starting()
                     .context("failed to fill in starting neofetch color codes")?;
 
-                let ColorProfile { colors } = color_profile.unique_colors();
+                let slot_colors = unique_slot_colors(custom_colors, color_profile)?;
 
                 // Apply colors
                 let asc = {
@@ -320,13 +425,10 @@ impl NormalizedAsciiArt {
                         .get_or_init(|| AhoCorasick::new(NEOFETCH_COLOR_PATTERNS).unwrap());
                     const N: usize = NEOFETCH_COLOR_PATTERNS.len();
                     let mut replacements = vec![Cow::from(""); N];
-                    for (&ai, &pi) in custom_colors {
-                        let ai: u8 = ai.into();
-                        let pi: u8 = pi.into();
-                        replacements[usize::from(ai.checked_sub(1).unwrap())] = colors
-                            [usize::from(pi)]
-                        .to_ansi_string(color_mode, ForegroundBackground::Foreground)
-                        .into();
+                    for (ai, c) in slot_colors {
+                        replacements[usize::from(u8::from(ai)).checked_sub(1).unwrap()] = c
+                            .to_ansi_string(color_mode, ForegroundBackground::Foreground)
+                            .into();
                     }
                     ac.replace_all(&asc, &replacements)
                 };
@@ -347,6 +449,242 @@ impl NormalizedAsciiArt {
         })
     }
 
+    /// Recolors the ascii art grapheme by grapheme, in the given style.
+    ///
+    /// Colors are only written when they change from the previous grapheme.
+    fn recolor_cells(
+        &self,
+        color_align: &ColorAlignment,
+        color_profile: &ColorProfile,
+        style: LogoStyle,
+        color_mode: AnsiMode,
+        theme: TerminalTheme,
+    ) -> Result<Vec<String>> {
+        if self.w == 0 {
+            return Ok(self.lines.clone());
+        }
+
+        let cell_color = self
+            .cell_colors(color_align, color_profile)
+            .context("failed to get colors of ascii cells")?;
+        let Self { lines, fg, .. } = self
+            .fill_starting()
+            .context("failed to fill in starting neofetch color codes")?;
+
+        let fore = color(
+            match theme {
+                TerminalTheme::Light => "&0",
+                TerminalTheme::Dark => "&f",
+            },
+            color_mode,
+        )
+        .expect("foreground color should not be invalid");
+        let reset = color("&~&*", color_mode).expect("color reset should not be invalid");
+        let reset_bg = color("&*", color_mode).expect("color reset should not be invalid");
+        let fg_ansi = |c: Srgb<u8>| c.to_ansi_string(color_mode, ForegroundBackground::Foreground);
+        let bg_ansi = |c: Srgb<u8>| c.to_ansi_string(color_mode, ForegroundBackground::Background);
+
+        let ac =
+            NEOFETCH_COLORS_AC.get_or_init(|| AhoCorasick::new(NEOFETCH_COLOR_PATTERNS).unwrap());
+        let lines = lines
+            .iter()
+            .enumerate()
+            .map(|(y, line)| {
+                let mut dst = String::new();
+                let mut current = None;
+                let mut x: usize = 0;
+                let mut matches = ac.find_iter(line).peekable();
+                // Spaces before the first color code take the color of that code
+                let mut indent = matches.peek().map(|m| &line[..m.start()]);
+                while let Some(m) = matches.next() {
+                    let ai_start = m.start().checked_add(3).unwrap();
+                    let ai_end = m.end().checked_sub(1).unwrap();
+                    let neofetch_color_idx: NeofetchAsciiIndexedColor = line[ai_start..ai_end]
+                        .parse()
+                        .expect("neofetch color index should be valid");
+                    let end = matches.peek().map_or(line.len(), |m_next| m_next.start());
+                    let is_fg = fg.contains(&neofetch_color_idx);
+
+                    for gr in indent
+                        .take()
+                        .into_iter()
+                        .chain([&line[m.end()..end]])
+                        .flat_map(|txt| txt.graphemes(true))
+                    {
+                        let code = match (style, cell_color(x, y, neofetch_color_idx)) {
+                            (LogoStyle::Foreground, _) if is_fg => fore.clone(),
+                            (LogoStyle::Both, _) if is_fg && gr != " " => {
+                                format!("{fore}{reset_bg}")
+                            },
+                            (_, None) => reset.clone(),
+                            (LogoStyle::Foreground, Some(c)) => fg_ansi(c),
+                            (LogoStyle::Background, Some(c)) => {
+                                let gray = c
+                                    .contrast_grayscale()
+                                    .to_ansi_string(color_mode, ForegroundBackground::Foreground);
+                                format!("{bg}{gray}", bg = bg_ansi(c))
+                            },
+                            (LogoStyle::Both, Some(c)) if gr == " " => bg_ansi(c),
+                            (LogoStyle::Both, Some(c)) => {
+                                format!("{fg}{reset_bg}", fg = fg_ansi(c))
+                            },
+                        };
+                        if current.as_ref() != Some(&code) {
+                            dst.push_str(&code);
+                            current = Some(code);
+                        }
+                        dst.push_str(gr);
+                        x = x.checked_add(1).unwrap();
+                    }
+                }
+                // Reset colors at end of each line to prevent color bleeding
+                dst.push_str(&reset);
+                dst
+            })
+            .collect();
+
+        Ok(lines)
+    }
+
+    /// Gets a function that maps the `(x, y)` coordinates and color slot of a
+    /// cell to its color, or `None` if the slot has no color.
+    fn cell_colors(
+        &self,
+        color_align: &ColorAlignment,
+        color_profile: &ColorProfile,
+    ) -> Result<CellColors> {
+        let cell_colors: CellColors = match color_align {
+            ColorAlignment::Horizontal => {
+                let ColorProfile { colors, .. } = color_profile
+                    .with_length(self.h.try_into().expect("`h` should not be 0"))
+                    .with_context(|| {
+                        format!("failed to spread color profile to length {h}", h = self.h)
+                    })?;
+                Box::new(move |_, y, _| Some(colors[y]))
+            },
+            ColorAlignment::Vertical => {
+                let ColorProfile { colors, .. } = color_profile
+                    .with_length(self.w.try_into().expect("`w` should not be 0"))
+                    .with_context(|| {
+                        format!("failed to spread color profile to length {w}", w = self.w)
+                    })?;
+                Box::new(move |x, _, _| Some(colors[x]))
+            },
+            &ColorAlignment::Diagonal { angle } => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let color_at = self
+                    .position_colors(move |x, y| x.mul_add(cos, y * sin), color_profile)
+                    .context("failed to get colors diagonally")?;
+                Box::new(move |x, y, _| Some(color_at(x, y)))
+            },
+            &ColorAlignment::Radial { center: (cx, cy) } => {
+                let cx = cx * f32::from(self.w.saturating_sub(1));
+                let cy = cy * f32::from(self.h.saturating_sub(1)) * CELL_ASPECT_RATIO;
+                let color_at = self
+                    .position_colors(move |x, y| (x - cx).hypot(y - cy), color_profile)
+                    .context("failed to get colors radially")?;
+                Box::new(move |x, y, _| Some(color_at(x, y)))
+            },
+            ColorAlignment::Custom {
+                colors: custom_colors,
+            } if !color_profile.parts.is_empty() => {
+                let slot_colors = self.part_slot_colors(custom_colors, color_profile)?;
+                Box::new(move |_, y, ai| {
+                    slot_colors
+                        .iter()
+                        .find(|&&(slot, _)| slot == ai)
+                        .map(|(_, colors)| colors[y])
+                })
+            },
+            ColorAlignment::Custom {
+                colors: custom_colors,
+            } => {
+                let slot_colors = unique_slot_colors(custom_colors, color_profile)?;
+                Box::new(move |_, _, ai| {
+                    slot_colors
+                        .iter()
+                        .find(|&&(slot, _)| slot == ai)
+                        .map(|&(_, c)| c)
+                })
+            },
+        };
+
+        Ok(cell_colors)
+    }
+
+    /// Gets a function that maps the `(x, y)` coordinates of a cell to its
+    /// color, by the position of the cell along a color axis.
+    ///
+    /// `pos` maps the `(x, y)` coordinates of a cell to a position along the
+    /// color axis. `y` is scaled by [`CELL_ASPECT_RATIO`], so that colors
+    /// spread evenly on the terminal.
+    fn position_colors<F>(
+        &self,
+        pos: F,
+        color_profile: &ColorProfile,
+    ) -> Result<impl Fn(usize, usize) -> Srgb<u8>>
+    where
+        F: Fn(f32, f32) -> f32,
+    {
+        let cell_pos = move |x: usize, y: usize| pos(x as f32, y as f32 * CELL_ASPECT_RATIO);
+        let (min, max) = (0..usize::from(self.h))
+            .cartesian_product(0..usize::from(self.w))
+            .map(|(y, x)| cell_pos(x, y))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), p| {
+                (min.min(p), max.max(p))
+            });
+
+        // One color step per cell along the axis
+        let length = ((max - min).ceil() as usize).saturating_add(1);
+        let length = u16::try_from(length).unwrap_or(u16::MAX);
+        let ColorProfile { colors, .. } = color_profile
+            .with_length(length.try_into().expect("`length` should not be 0"))
+            .with_context(|| format!("failed to spread color profile to length {length}"))?;
+        let last_i = usize::from(length.checked_sub(1).unwrap());
+        let color_i = move |p: f32| {
+            if max > min {
+                cmp::min(
+                    ((p - min) / (max - min) * last_i as f32).round() as usize,
+                    last_i,
+                )
+            } else {
+                0
+            }
+        };
+
+        Ok(move |x, y| colors[color_i(cell_pos(x, y))])
+    }
+
+    /// Spreads each of the combined color profiles over the height of the ascii
+    /// art, for the color slots of a custom alignment.
+    fn part_slot_colors(
+        &self,
+        custom_colors: &IndexMap<NeofetchAsciiIndexedColor, PresetIndexedColor>,
+        color_profile: &ColorProfile,
+    ) -> Result<Vec<(NeofetchAsciiIndexedColor, Vec<Srgb<u8>>)>> {
+        // Each slot gets a whole color profile, as horizontal stripes
+        let parts = color_profile.parts();
+        custom_colors
+            .iter()
+            .map(|(&ai, &pi)| {
+                let pi: u8 = pi.into();
+                let part = parts.get(usize::from(pi)).with_context(|| {
+                    format!(
+                        "custom color index {pi} should be less than the number of combined \
+                         presets ({len})",
+                        len = parts.len()
+                    )
+                })?;
+                let ColorProfile { colors, .. } = part
+                    .with_length(self.h.try_into().expect("`h` should not be 0"))
+                    .with_context(|| {
+                        format!("failed to spread color profile to length {h}", h = self.h)
+                    })?;
+                Ok((ai, colors))
+            })
+            .collect()
+    }
+
     /// Fills the missing starting placeholders.
     ///
     /// e.g. `"${c1}...\n..."` -> `"${c1}...\n${c1}..."`
@@ -400,3 +738,87 @@ impl NormalizedAsciiArt {
         })
     }
 }
+
+/// Picks the unique color of the preset for each color slot of a custom
+/// alignment.
+fn unique_slot_colors(
+    custom_colors: &IndexMap<NeofetchAsciiIndexedColor, PresetIndexedColor>,
+    color_profile: &ColorProfile,
+) -> Result<Vec<(NeofetchAsciiIndexedColor, Srgb<u8>)>> {
+    let ColorProfile { colors, .. } = color_profile.unique_colors();
+    custom_colors
+        .iter()
+        .map(|(&ai, &pi)| {
+            let pi: u8 = pi.into();
+            let c = colors.get(usize::from(pi)).with_context(|| {
+                format!(
+                    "custom color index {pi} should be less than the number of unique colors in \
+                     the preset ({len})",
+                    len = colors.len()
+                )
+            })?;
+            Ok((ai, *c))
+        })
+        .collect()
+}
+
+fn color_slots<'a, I>(lines: I) -> Vec<NeofetchAsciiIndexedColor>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let ac = NEOFETCH_COLORS_AC.get_or_init(|| AhoCorasick::new(NEOFETCH_COLOR_PATTERNS).unwrap());
+    let slots: BTreeSet<NeofetchAsciiIndexedColor> = lines
+        .into_iter()
+        .flat_map(|line| ac.find_iter(line))
+        .map(|m| {
+            // Patterns are in order, from `${c1}`
+            let slot = m.pattern().as_usize().checked_add(1).unwrap();
+            u8::try_from(slot)
+                .ok()
+                .and_then(|slot| slot.try_into().ok())
+                .expect("pattern index should map to a valid neofetch color index")
+        })
+        .collect();
+    slots.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::presets::Preset;
+
+    #[test]
+    fn mismatched_custom_alignment_falls_back_to_horizontal() {
+        let asc = RawAsciiArt {
+            asc: "${c1}hy\n${c2}fetch".to_owned(),
+            fg: Vec::new(),
+        }
+        .to_normalized()
+        .unwrap();
+        let color_profile = Preset::Rainbow.color_profile();
+        let recolor = |color_align: &ColorAlignment| {
+            asc.to_recolored(
+                color_align,
+                &color_profile,
+                LogoStyle::Foreground,
+                AnsiMode::Rgb,
+                TerminalTheme::Dark,
+            )
+            .unwrap()
+            .lines
+        };
+        let custom = |slots: &[(u8, u8)]| ColorAlignment::Custom {
+            colors: slots
+                .iter()
+                .map(|&(ai, pi)| (ai.try_into().unwrap(), PresetIndexedColor::from(pi)))
+                .collect(),
+        };
+
+        let horizontal = recolor(&ColorAlignment::Horizontal);
+        // Slot c3 is not in the ascii art
+        assert_eq!(recolor(&custom(&[(1, 0), (2, 1), (3, 2)])), horizontal);
+        // Slot c2 has no color
+        assert_eq!(recolor(&custom(&[(1, 0)])), horizontal);
+        assert_ne!(recolor(&custom(&[(1, 0), (2, 1)])), horizontal);
+    }
+}
@@ -2,37 +2,43 @@ use std::borrow::Cow;
 use std::cmp;
 use std::fmt::Write as _;
 use std::fs::{self, File};
-use std::io::{self, IsTerminal as _, Read as _, Write as _};
+use std::io::{self, IsTerminal as _, Write as _};
 use std::iter::zip;
 use std::num::NonZeroU8;
 use std::path::{Path, PathBuf};
 
 use aho_corasick::AhoCorasick;
-use anyhow::{Context as _, Result};
+use anyhow::{anyhow, Context as _, Result};
 use deranged::RangedU8;
 use enterpolation::bspline::BSpline;
 use enterpolation::{Curve as _, Generator as _};
 use hyfetch::ascii::RawAsciiArt;
-use hyfetch::cli_options::options;
+use hyfetch::cli_options::{options, Command, ListCommand, Logo, Options, PreviewCommand};
 use hyfetch::color_util::{
     clear_screen, color, printc, ContrastGrayscale as _, ForegroundBackground, Lightness,
     NeofetchAsciiIndexedColor, PresetIndexedColor, Theme as _, ToAnsiString as _,
 };
-use hyfetch::models::Config;
+use hyfetch::config_layers::ConfigLayers;
+use hyfetch::distro_cache;
+use hyfetch::distros::Distro;
+use hyfetch::image::{self, RgbaImage};
+use hyfetch::models::{
+    read_custom_presets, save_created_config, Config, CONFIG_VERSION, PAGE_NAVIGATION_WORDS,
+};
 #[cfg(feature = "macchina")]
 use hyfetch::neofetch_util::macchina_path;
-use hyfetch::neofetch_util::{self, add_pkg_path, fastfetch_path, get_distro_ascii, literal_input, ColorAlignment, NEOFETCH_COLORS_AC, NEOFETCH_COLOR_PATTERNS, TEST_ASCII};
-use hyfetch::presets::{AssignLightness, Preset};
-use hyfetch::pride_month;
-use hyfetch::types::{AnsiMode, Backend, TerminalTheme};
+use hyfetch::neofetch_util::{self, add_pkg_path, default_backend, fastfetch_path, get_distro_ascii, literal_input, ColorAlignment, DEFAULT_DIAGONAL_ANGLE, DEFAULT_RADIAL_CENTER, NEOFETCH_COLORS_AC, NEOFETCH_COLOR_PATTERNS, TEST_ASCII};
+use hyfetch::pixel_flag::pixel_flag;
+use hyfetch::presets::{AnyPreset, AssignLightness, CustomPreset, Preset};
+use hyfetch::{export, list, preview, pride_month};
+use hyfetch::tui;
+use hyfetch::types::{AnsiMode, Backend, Blend, LogoStyle, OutputFormat, TerminalTheme};
 use hyfetch::utils::{get_cache_path, input};
 use hyfetch::font_logo::get_font_logo;
 use indexmap::{IndexMap, IndexSet};
 use itertools::Itertools as _;
 use palette::{LinSrgb, Srgb};
-use serde::Serialize as _;
-use serde_json::ser::PrettyFormatter;
-use strum::{EnumCount as _, VariantArray, VariantNames};
+use strum::VariantNames;
 use terminal_colorsaurus::{background_color, QueryOptions};
 use terminal_size::{terminal_size, Height, Width};
 use time::{Month, OffsetDateTime};
@@ -57,36 +63,154 @@ fn main() -> Result<()> {
     // Use a custom distro
     let distro = options.distro.as_ref();
 
-    let backend = options.backend.unwrap_or_else(|| {
-        if fastfetch_path().is_ok() { Backend::Fastfetch } else { Backend::Neofetch }
-    });
+    // Looking for fastfetch takes a while, so only do it when needed
+    let backend = || options.backend.unwrap_or_else(default_backend);
+
+    if options.refresh {
+        distro_cache::clear().context("failed to clear distro cache")?;
+    }
 
     if options.test_print {
-        let asc = get_distro_ascii(distro, backend).context("failed to get distro ascii")?;
+        let (asc, _) = get_distro_ascii(distro, backend()).context("failed to get distro ascii")?;
         writeln!(io::stdout(), "{asc}", asc = asc.asc)
             .context("failed to write ascii to stdout")?;
         return Ok(());
     }
 
     if options.print_font_logo {
-        let logo = get_font_logo(backend).context("failed to get font logo")?;
+        let logo = get_font_logo(backend()).context("failed to get font logo")?;
         writeln!(io::stdout(), "{}", logo).context("failed to write logo to stdout")?;
         return Ok(());
     }
 
-    let config = if options.config {
-        create_config(&options.config_file, distro, backend, debug_mode)
-            .context("failed to create config")?
-    } else if let Some(config) =
-        load_config(&options.config_file).context("failed to load config")?
-    {
-        config
+    if options.config && options.non_interactive {
+        let (config, distro_variant) = create_config_non_interactive(&options, backend())
+            .context("failed to create config")?;
+        save_created_config(
+            &options.config_file,
+            &config,
+            distro_variant.as_ref(),
+            options.profile.as_deref(),
+        )
+        .context("failed to save config")?;
+        writeln!(
+            io::stdout(),
+            "Config saved to {path}",
+            path = options.config_file.display()
+        )
+        .context("failed to write message to stdout")?;
+        return Ok(());
+    }
+
+    let mut layers = ConfigLayers::load(&options).context("failed to load config")?;
+
+    if let Some(command) = &options.command {
+        // Use the config if there is one, but don't ask to create it
+        let mut config = match layers.resolve().context("failed to resolve config")? {
+            Some(resolved) => resolved.config,
+            None => {
+                create_config_non_interactive(&options, backend())
+                    .context("failed to create config")?
+                    .0
+            },
+        };
+        let out = match command {
+            Command::List(ListCommand::Presets { format }) => {
+                list::list_presets(&config.custom_presets, config.mode, *format)
+                    .context("failed to list presets")?
+            },
+            Command::List(ListCommand::Distros { format }) => {
+                list::list_distros(*format).context("failed to list distros")?
+            },
+            Command::Preview(PreviewCommand {
+                presets,
+                color_align,
+            }) => {
+                let (asc, distro_variant) =
+                    get_distro_ascii(config.distro.as_ref(), config.backend)
+                        .context("failed to get distro ascii")?;
+                let asc = asc.to_normalized().context("failed to normalize ascii")?;
+                let presets: Vec<AnyPreset> = match presets {
+                    Some(names) => names
+                        .iter()
+                        .map(|name| {
+                            AnyPreset::from_name(name, &config.custom_presets)
+                                .with_context(|| format!("failed to find preset {name:?}"))
+                        })
+                        .collect::<Result<_>>()?,
+                    None => AnyPreset::all(&config.custom_presets).collect(),
+                };
+                config.color_align = match color_align {
+                    Some(color_align) => color_align.clone(),
+                    None => config.color_align_for(distro_variant.as_ref()).clone(),
+                };
+                let width = terminal_size()
+                    .map(|(Width(w), _)| w)
+                    .unwrap_or(preview::FALLBACK_WIDTH);
+                let out = preview::preview_presets(&asc, &presets, &config, width)
+                    .context("failed to preview presets")?;
+                match options.output {
+                    Some(OutputFormat::Svg) => export::to_svg(&out, config.light_dark),
+                    Some(OutputFormat::Html) => export::to_html(&out, config.light_dark),
+                    None => out,
+                }
+            },
+        };
+        if let Some(path) = &options.output_file {
+            fs::write(path, out).with_context(|| format!("failed to write to file {path:?}"))?;
+        } else {
+            io::stdout()
+                .write_all(out.as_bytes())
+                .context("failed to write to stdout")?;
+        }
+        return Ok(());
+    }
+
+    let resolved = if options.config {
+        // The profile being created may not exist yet
+        None
     } else {
-        create_config(&options.config_file, distro, backend, debug_mode)
-            .context("failed to create config")?
+        layers.resolve().context("failed to resolve config")?
+    };
+    let resolved = match resolved {
+        Some(resolved) => resolved,
+        None if options.print_config => {
+            return Err(anyhow!(
+                "config should specify a preset and color mode; run `hyfetch --config` to create \
+                 one"
+            ));
+        },
+        None => {
+            let custom_presets = read_custom_presets(&options.config_file)
+                .context("failed to read custom presets from config")?;
+            let config = create_config(
+                &options.config_file,
+                options.profile.as_deref(),
+                distro,
+                backend(),
+                custom_presets,
+                debug_mode,
+            )
+            .context("failed to create config")?;
+            layers
+                .set_user_config(&options.config_file, &config)
+                .context("failed to set user config")?;
+            layers
+                .resolve()
+                .context("failed to resolve config")?
+                .expect("config should be complete after creating the user config")
+        },
     };
 
-    let color_mode = options.mode.unwrap_or(config.mode);
+    if options.print_config {
+        for line in resolved.to_lines().context("failed to format config")? {
+            writeln!(io::stdout(), "{line}").context("failed to write config to stdout")?;
+        }
+        return Ok(());
+    }
+
+    let config = resolved.config;
+    let color_mode = config.mode;
     let theme = config.light_dark;
 
     // Check if it's June (pride month)
@@ -115,14 +239,21 @@ fn main() -> Result<()> {
     }
 
     // Use a custom distro
-    let distro = options.distro.as_ref().or(config.distro.as_ref());
+    let distro = config.distro.as_ref();
 
-    let backend = options.backend.unwrap_or(config.backend);
-    let args = options.args.as_ref().or(config.args.as_ref());
+    let backend = config.backend;
+    let args = config.args.as_ref();
 
     // Get preset
-    let preset = options.preset.unwrap_or(config.preset);
-    let color_profile = preset.color_profile();
+    let preset = {
+        let name = config.preset.as_ref();
+        AnyPreset::from_name(name, &config.custom_presets)
+            .with_context(|| format!("failed to find preset {name:?}"))?
+    };
+    let color_profile = preset
+        .color_profile(&config.custom_presets)
+        .with_context(|| format!("failed to get color profile of preset {preset:?}"))?
+        .with_blend(config.blend);
     debug!(?color_profile, "color profile");
 
     // Lighten
@@ -135,21 +266,51 @@ fn main() -> Result<()> {
     };
     debug!(?color_profile, "lightened color profile");
 
-    let asc = if let Some(path) = options.ascii_file {
-        RawAsciiArt {
-            asc: fs::read_to_string(&path)
-                .with_context(|| format!("failed to read ascii from {path:?}"))?,
-            fg: Vec::new(),
-        }
+    let asc = if let Some(Logo::PixelFlag(size)) = options.logo {
+        pixel_flag(&color_profile, size, color_mode).context("failed to draw pixel flag")?
     } else {
-        get_distro_ascii(distro, backend).context("failed to get distro ascii")?
+        let (asc, distro_variant) = match options.logo {
+            Some(Logo::AsciiFile(path)) => {
+                let asc = RawAsciiArt {
+                    asc: fs::read_to_string(&path)
+                        .with_context(|| format!("failed to read ascii from {path:?}"))?,
+                    fg: Vec::new(),
+                };
+                (asc, None)
+            },
+            Some(Logo::Image { path, charset }) => {
+                let asc = RgbaImage::open(path)
+                    .context("failed to open logo image")?
+                    .to_ascii(charset.unwrap_or_default(), image::DEFAULT_WIDTH)
+                    .context("failed to draw logo image as ascii")?;
+                (asc, None)
+            },
+            Some(Logo::PixelFlag(_)) | None => {
+                get_distro_ascii(distro, backend).context("failed to get distro ascii")?
+            },
+        };
+        let color_align = config.color_align_for(distro_variant.as_ref());
+        debug!(?color_align, "color alignment");
+        let asc = asc.to_normalized().context("failed to normalize ascii")?;
+        asc.to_recolored(color_align, &color_profile, config.style, color_mode, theme)
+            .context("failed to recolor ascii")?
     };
-    let asc = asc.to_normalized().context("failed to normalize ascii")?;
-    let color_align = config.color_align;
-    let asc = asc
-        .to_recolored(&color_align, &color_profile, color_mode, theme)
-        .context("failed to recolor ascii")?;
-    neofetch_util::run(asc, backend, args)?;
+    if let Some(output) = options.output {
+        let out = neofetch_util::run_captured(asc, backend, args)?;
+        let out = match output {
+            OutputFormat::Svg => export::to_svg(&out, theme),
+            OutputFormat::Html => export::to_html(&out, theme),
+        };
+        if let Some(path) = &options.output_file {
+            fs::write(path, out).with_context(|| format!("failed to write to file {path:?}"))?;
+        } else {
+            io::stdout()
+                .write_all(out.as_bytes())
+                .context("failed to write to stdout")?;
+        }
+    } else {
+        neofetch_util::run(asc, backend, args)?;
+    }
 
     if options.ask_exit {
         input(Some("Press enter to exit...")).context("failed to read input")?;
@@ -158,82 +319,34 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-/// Loads config from file.
-///
-/// Returns `None` if the config file does not exist.
-#[tracing::instrument(level = "debug")]
-fn load_config(path: &PathBuf) -> Result<Option<Config>> {
-    let mut file = match File::open(path) {
-        Ok(file) => file,
-        Err(err) if err.kind() == io::ErrorKind::NotFound => {
-            return Ok(None);
-        },
-        Err(err) => {
-            return Err(err).with_context(|| format!("failed to open file {path:?} for reading"));
-        },
-    };
-
-    let mut buf = String::new();
-
-    file.read_to_string(&mut buf)
-        .with_context(|| format!("failed to read from file {path:?}"))?;
-
-    let deserializer = &mut serde_json::Deserializer::from_str(&buf);
-    let config: Config = serde_path_to_error::deserialize(deserializer)
-        .with_context(|| format!("failed to parse config from file {path:?}"))?;
-
-    debug!(?config, "loaded config");
-
-    Ok(Some(config))
-}
-
 /// Creates config interactively.
 ///
 /// The config is automatically stored to file.
-#[tracing::instrument(level = "debug")]
+#[tracing::instrument(level = "debug", skip(custom_presets))]
 fn create_config(
     path: &PathBuf,
+    profile: Option<&str>,
     distro: Option<&String>,
     backend: Backend,
+    custom_presets: IndexMap<String, CustomPreset>,
     debug_mode: bool,
 ) -> Result<Config> {
     // Detect terminal environment (doesn't work for all terminal emulators,
     // especially on Windows)
-    let det_bg = if io::stdout().is_terminal() {
-        match background_color(QueryOptions::default()) {
-            Ok(bg) => Some(Srgb::<u16>::new(bg.r, bg.g, bg.b).into_format::<u8>()),
-            Err(terminal_colorsaurus::Error::UnsupportedTerminal) => None,
-            Err(err) => {
-                return Err(err).context("failed to get terminal background color");
-            },
-        }
-    } else {
-        None
-    };
-    debug!(?det_bg, "detected background color");
-    let det_ansi = supports_color::on(supports_color::Stream::Stdout).map(|color_level| {
-        if color_level.has_16m {
-            AnsiMode::Rgb
-        } else if color_level.has_256 {
-            AnsiMode::Ansi256
-        } else if color_level.has_basic {
-            // unimplemented!(
-            //     "{mode} color mode not supported",
-            //     mode = AnsiMode::Ansi16.as_ref()
-            // );
-            AnsiMode::Ansi256
-        } else {
-            unreachable!();
-        }
-    });
-    debug!(?det_ansi, "detected color mode");
+    let det_bg = detect_background_color().context("failed to detect background color")?;
+    let det_ansi = detect_color_mode();
 
-    let asc = get_distro_ascii(distro, backend).context("failed to get distro ascii")?;
+    let (asc, distro_variant) =
+        get_distro_ascii(distro, backend).context("failed to get distro ascii")?;
     let asc = asc.to_normalized().context("failed to normalize ascii")?;
     let theme = det_bg.map(|bg| bg.theme()).unwrap_or(TerminalTheme::Light);
     let color_mode = det_ansi.unwrap_or(AnsiMode::Ansi256);
     let mut title = format!(
-        "Welcome to {logo} Let's set up some colors first.",
+        "Welcome to {logo} Let's set up some colors {what}.",
+        what = match profile {
+            Some(name) => format!("for profile {name:?}"),
+            None => "first".to_owned(),
+        },
         logo = color(
             match theme {
                 TerminalTheme::Light => "&l&bhyfetch&~&L",
@@ -243,6 +356,44 @@ fn create_config(
         )
         .expect("logo should not contain invalid color codes")
     );
+
+    if tui::is_supported() && !debug_mode {
+        let choices = tui::run_wizard(
+            &title,
+            &asc,
+            &custom_presets,
+            det_ansi,
+            det_bg.map(|bg| bg.theme()),
+            backend,
+        )
+        .context("failed to run config wizard")?
+        .context("config wizard was cancelled")?;
+        let mut config = Config {
+            version: CONFIG_VERSION,
+            preset: choices.preset,
+            mode: choices.color_mode,
+            light_dark: choices.theme,
+            lightness: Some(choices.lightness),
+            // Set below, as custom alignments are stored for the distro
+            color_align: ColorAlignment::Horizontal,
+            distro_color_align: IndexMap::new(),
+            blend: Blend::default(),
+            style: LogoStyle::default(),
+            backend: choices.backend,
+            args: None,
+            distro: distro.cloned(),
+            pride_month_disable: false,
+            custom_presets,
+            profiles: IndexMap::new(),
+            extra: IndexMap::new(),
+        };
+        config.set_color_align(choices.color_align, distro_variant.as_ref());
+        debug!(?config, "created config");
+        ask_save_config(path, &config, distro_variant.as_ref(), profile)
+            .context("failed to save config")?;
+        return Ok(config);
+    }
+
     clear_screen(Some(&title), color_mode, debug_mode).context("failed to clear screen")?;
 
     let mut option_counter = NonZeroU8::new(1).unwrap();
@@ -274,14 +425,7 @@ fn create_config(
     {
         let (Width(term_w), Height(term_h)) =
             terminal_size().context("failed to get terminal size")?;
-        let (term_w_min, term_h_min) = (
-            u16::from(asc.w)
-                .checked_mul(2)
-                .unwrap()
-                .checked_add(4)
-                .unwrap(),
-            30,
-        );
+        let (term_w_min, term_h_min) = (asc.w.checked_mul(2).unwrap().checked_add(4).unwrap(), 30);
         if term_w < term_w_min || term_h < term_h_min {
             printc(
                 format!(
@@ -335,6 +479,27 @@ fn create_config(
             (t - a) * ((d - c) / (b - a)) + c
         }
 
+        {
+            let label = format!(
+                "{label:^term_w$}",
+                label = "16 Color Testing",
+                term_w = usize::from(term_w)
+            );
+            let line = zip(gradient.iter(), label.chars()).fold(
+                String::new(),
+                |mut s, (&rgb_f32_color, t)| {
+                    let rgb_u8_color = Srgb::<u8>::from_linear(rgb_f32_color);
+                    let back = rgb_u8_color
+                        .to_ansi_string(AnsiMode::Ansi16, ForegroundBackground::Background);
+                    let fore = rgb_u8_color
+                        .contrast_grayscale()
+                        .to_ansi_string(AnsiMode::Ansi16, ForegroundBackground::Foreground);
+                    write!(s, "{back}{fore}{t}").unwrap();
+                    s
+                },
+            );
+            printc(line, AnsiMode::Ansi16).context("failed to print 16-color test line")?;
+        }
         {
             let label = format!(
                 "{label:^term_w$}",
@@ -387,7 +552,8 @@ fn create_config(
         .context("failed to print title prompt")?;
         writeln!(
             io::stdout(),
-            "(If you can't see colors under \"RGB Color Testing\", please choose 8bit)\n"
+            "(If you can't see colors under \"RGB Color Testing\", please choose 8bit. If you \
+             can't see colors under \"8bit Color Testing\" either, please choose 16)\n"
         )
         .context("failed to write message to stdout")?;
 
@@ -453,74 +619,60 @@ fn create_config(
     // 3. Choose preset
 
     // Create flag lines
-    let mut flags = Vec::with_capacity(Preset::COUNT);
+    let presets: Vec<AnyPreset> = AnyPreset::all(&custom_presets).collect();
+    let mut flags = Vec::with_capacity(presets.len());
+    let (Width(term_w), Height(term_h)) = terminal_size().context("failed to get terminal size")?;
     let spacing = {
-        let spacing = <Preset as VariantNames>::VARIANTS
+        let spacing = presets
             .iter()
-            .map(|name| name.chars().count())
+            .map(|preset| preset.as_ref().chars().count())
             .max()
-            .expect("preset name iterator should not be empty");
-        let spacing: u8 = spacing.try_into().expect("`spacing` should fit in `u8`");
-        cmp::max(spacing, 20)
+            .expect("preset iterator should not be empty");
+        // Long custom preset names are cut off to fit in the terminal
+        cmp::max(
+            1,
+            cmp::min(cmp::max(spacing, 20), usize::from(term_w.saturating_sub(2))),
+        )
     };
-    for preset in <Preset as VariantArray>::VARIANTS {
-        let color_profile = preset.color_profile();
+    for preset in &presets {
+        let color_profile = preset
+            .color_profile(&custom_presets)
+            .with_context(|| format!("failed to get color profile of preset {preset:?}"))?;
         let flag = color_profile
             .color_text(
-                " ".repeat(usize::from(spacing)),
+                " ".repeat(spacing),
                 color_mode,
                 ForegroundBackground::Background,
                 false,
             )
             .with_context(|| format!("failed to color flag using preset: {preset:?}"))?;
-        let name = format!(
-            "{name:^spacing$}",
-            name = preset.as_ref(),
-            spacing = usize::from(spacing)
-        );
+        let name: String = preset.as_ref().chars().take(spacing).collect();
+        let name = format!("{name:^spacing$}");
         flags.push([name, flag.clone(), flag.clone(), flag]);
     }
 
     // Calculate flags per row
-    let (flags_per_row, rows_per_page) = {
-        let (Width(term_w), Height(term_h)) =
-            terminal_size().context("failed to get terminal size")?;
-        let flags_per_row = term_w.div_euclid(u16::from(spacing).checked_add(2).unwrap());
-        let flags_per_row: u8 = flags_per_row
-            .try_into()
-            .expect("`flags_per_row` should fit in `u8`");
-        let rows_per_page = cmp::max(1, term_h.saturating_sub(13).div_euclid(5));
-        let rows_per_page: u8 = rows_per_page
-            .try_into()
-            .expect("`rows_per_page` should fit in `u8`");
-        (flags_per_row, rows_per_page)
-    };
-    let num_pages =
-        u16::from(u8::try_from(Preset::COUNT).expect("`Preset::COUNT` should fit in `u8`"))
-            .div_ceil(
-                u16::from(flags_per_row)
-                    .checked_mul(u16::from(rows_per_page))
-                    .unwrap(),
-            );
-    let num_pages: u8 = num_pages
-        .try_into()
-        .expect("`num_pages` should fit in `u8`");
+    let flags_per_row = cmp::max(
+        1,
+        usize::from(term_w).div_euclid(spacing.checked_add(2).unwrap()),
+    );
+    let rows_per_page = cmp::max(1, usize::from(term_h.saturating_sub(13).div_euclid(5)));
+    let flags_per_page = flags_per_row
+        .checked_mul(rows_per_page)
+        .context("number of flags per page should not overflow")?;
+    let num_pages = flags.len().div_ceil(flags_per_page);
 
     // Create pages
-    let mut pages = Vec::with_capacity(usize::from(num_pages));
-    for flags in flags.chunks(usize::from(
-        u16::from(flags_per_row)
-            .checked_mul(u16::from(rows_per_page))
-            .unwrap(),
-    )) {
-        let mut page = Vec::with_capacity(usize::from(rows_per_page));
-        for flags in flags.chunks(usize::from(flags_per_row)) {
+    let mut pages = Vec::with_capacity(num_pages);
+    for flags in flags.chunks(flags_per_page) {
+        let mut page = Vec::with_capacity(rows_per_page);
+        for flags in flags.chunks(flags_per_row) {
             page.push(flags);
         }
         pages.push(page);
     }
 
-    let print_flag_page = |page, page_num: u8| -> Result<()> {
+    let print_flag_page = |page, page_num: usize| -> Result<()> {
         clear_screen(Some(&title), color_mode, debug_mode).context("failed to clear screen")?;
         print_title_prompt(option_counter, "Let's choose a flag!", color_mode)
             .context("failed to print title prompt")?;
@@ -560,15 +712,15 @@ fn create_config(
         )
         .expect("coloring text with default preset should not fail");
 
-    let preset: Preset;
+    let preset: AnyPreset;
     let color_profile;
 
-    let mut page: u8 = 0;
+    let mut page: usize = 0;
     loop {
-        print_flag_page(&pages[usize::from(page)], page).context("failed to print flag page")?;
+        print_flag_page(&pages[page], page).context("failed to print flag page")?;
 
-        let mut opts: Vec<&str> = <Preset as VariantNames>::VARIANTS.into();
-        opts.extend(["next", "n", "prev", "p"]);
+        let mut opts: Vec<&str> = presets.iter().map(AsRef::as_ref).collect();
+        opts.extend(PAGE_NAVIGATION_WORDS);
 
         writeln!(
             io::stdout(),
@@ -588,13 +740,22 @@ fn create_config(
         .context("failed to ask for choice input")
         .context("failed to select preset")?;
         if selection == "next" || selection == "n" {
-            page = (page + 1) % num_pages;
+            page = page.checked_add(1).unwrap().checked_rem(num_pages).unwrap();
         } else if selection == "prev" || selection == "p" {
-            page = (page + num_pages - 1) % num_pages;
+            page = page
+                .checked_add(num_pages)
+                .unwrap()
+                .checked_sub(1)
+                .unwrap()
+                .checked_rem(num_pages)
+                .unwrap();
         } else {
-            preset = selection.parse().expect("selected preset should be valid");
+            preset = AnyPreset::from_name(selection, &custom_presets)
+                .expect("selected preset should be valid");
             debug!(?preset, "selected preset");
-            color_profile = preset.color_profile();
+            color_profile = preset
+                .color_profile(&custom_presets)
+                .expect("selected preset should have a valid color profile");
             update_title(
                 &mut title,
                 &mut option_counter,
@@ -650,10 +811,7 @@ fn create_config(
         // Print cats
         {
             let (Width(term_w), _) = terminal_size().context("failed to get terminal size")?;
-            let num_cols = cmp::max(
-                1,
-                term_w.div_euclid(u16::from(test_ascii.w).checked_add(2).unwrap()),
-            );
+            let num_cols = cmp::max(1, term_w.div_euclid(test_ascii.w.checked_add(2).unwrap()));
             let num_cols: u8 = num_cols.try_into().expect("`num_cols` should fit in `u8`");
             const MIN: f32 = 0.15;
             const MAX: f32 = 0.85;
@@ -688,6 +846,7 @@ fn create_config(
                                     .expect("generated lightness should not be invalid"),
                                 theme,
                             ),
+                            LogoStyle::Foreground,
                             color_mode,
                             theme,
                         )
@@ -772,10 +931,7 @@ fn create_config(
     let (ascii_per_row, ascii_rows) = {
         let (Width(term_w), Height(term_h)) =
             terminal_size().context("failed to get terminal size")?;
-        let ascii_per_row = cmp::max(
-            1,
-            term_w.div_euclid(u16::from(asc.w).checked_add(2).unwrap()),
-        );
+        let ascii_per_row = cmp::max(1, term_w.div_euclid(asc.w.checked_add(2).unwrap()));
         let ascii_per_row: u8 = ascii_per_row
             .try_into()
             .expect("`ascii_per_row` should fit in `u8`");
@@ -783,7 +939,7 @@ fn create_config(
             1,
             term_h
                 .saturating_sub(8)
-                .div_euclid(u16::from(asc.h).checked_add(1).unwrap()),
+                .div_euclid(asc.h.checked_add(1).unwrap()),
         );
         let ascii_rows: u8 = ascii_rows
             .try_into()
@@ -791,11 +947,23 @@ fn create_config(
         (ascii_per_row, ascii_rows)
     };
 
-    // Displays horizontal and vertical arrangements in the first iteration, but
-    // hide them in later iterations
+    // Displays horizontal, vertical, diagonal and radial arrangements in the first
+    // iteration, but hide them in later iterations
     let hv_arrangements = [
         ("Horizontal", ColorAlignment::Horizontal),
         ("Vertical", ColorAlignment::Vertical),
+        (
+            "Diagonal",
+            ColorAlignment::Diagonal {
+                angle: DEFAULT_DIAGONAL_ANGLE,
+            },
+        ),
+        (
+            "Radial",
+            ColorAlignment::Radial {
+                center: DEFAULT_RADIAL_CENTER,
+            },
+        ),
     ];
     let mut arrangements: IndexMap<Cow<str>, ColorAlignment> =
         hv_arrangements.map(|(k, ca)| (k.into(), ca)).into();
@@ -823,8 +991,12 @@ fn create_config(
         // Random color schemes
         let mut preset_indices: Vec<PresetIndexedColor> =
             (0..color_profile.unique_colors().colors.len())
-                .map(|pi| u8::try_from(pi).expect("`pi` should fit in `u8`").into())
-                .collect();
+                .map(|pi| {
+                    u8::try_from(pi)
+                        .map(PresetIndexedColor::from)
+                        .context("preset should not have more than 256 unique colors")
+                })
+                .collect::<Result<_>>()?;
         while preset_indices.len() < slots.len() {
             preset_indices.extend_from_within(0..);
         }
@@ -868,7 +1040,7 @@ fn create_config(
             .iter()
             .map(|(k, ca)| {
                 let mut v: Vec<String> = asc
-                    .to_recolored(ca, &color_profile, color_mode, theme)
+                    .to_recolored(ca, &color_profile, LogoStyle::Foreground, color_mode, theme)
                     .context("failed to recolor ascii")?
                     .lines;
                 v.push(format!("{k:^asc_width$}", asc_width = usize::from(asc.w)));
@@ -898,11 +1070,13 @@ fn create_config(
         .context("failed to print title prompt")?;
         writeln!(
             io::stdout(),
-            "You can choose standard horizontal or vertical alignment, or use one of the random \
-             color schemes.\nYou can type \"roll\" to randomize again.\n"
+            "You can choose standard horizontal, vertical, diagonal or radial alignment, or use \
+             one of the random color schemes.\nYou can type \"roll\" to randomize again.\n"
         )
         .context("failed to write message to stdout")?;
-        let mut opts: Vec<Cow<str>> = ["horizontal", "vertical", "roll"].map(Into::into).into();
+        let mut opts: Vec<Cow<str>> = ["horizontal", "vertical", "diagonal", "radial", "roll"]
+            .map(Into::into)
+            .into();
         opts.extend((0..random_count).map(|i| format!("random{i}").into()));
         let choice = literal_input("Your choice?", &opts[..], "horizontal", true, color_mode)
             .context("failed to ask for choice input")
@@ -976,6 +1150,11 @@ fn create_config(
             color_mode,
         )
         .context("failed to print message")?;
+        printc(
+            "- &bnative&r: Built into hyfetch, &nno dependencies&r, fewer details\n",
+            color_mode,
+        )
+        .context("failed to print message")?;
 
         let choice = literal_input(
             "Your choice?",
@@ -998,47 +1177,158 @@ fn create_config(
 
     // Create config
     clear_screen(Some(&title), color_mode, debug_mode).context("failed to clear screen")?;
-    let config = Config {
+    let mut config = Config {
+        version: CONFIG_VERSION,
         preset,
         mode: color_mode,
         light_dark: theme,
         lightness: Some(lightness),
-        color_align,
+        // Set below, as custom alignments are stored for the distro
+        color_align: ColorAlignment::Horizontal,
+        distro_color_align: IndexMap::new(),
+        blend: Blend::default(),
+        style: LogoStyle::default(),
         backend,
         args: None,
         distro: distro.cloned(),
         pride_month_disable: false,
+        custom_presets,
+        profiles: IndexMap::new(),
+        extra: IndexMap::new(),
     };
+    config.set_color_align(color_align, distro_variant.as_ref());
     debug!(?config, "created config");
+    ask_save_config(path, &config, distro_variant.as_ref(), profile)
+        .context("failed to save config")?;
+
+    Ok(config)
+}
 
-    // Save config
-    let save = literal_input("Save config?", &["y", "n"], "y", true, color_mode)
+/// Asks whether to save the created config, and saves it if so.
+fn ask_save_config(
+    path: &Path,
+    config: &Config,
+    distro: Option<&Distro>,
+    profile: Option<&str>,
+) -> Result<()> {
+    let save = literal_input("Save config?", &["y", "n"], "y", true, config.mode)
         .context("failed to ask for choice input")?;
     if save == "y" {
-        match path.parent().context("invalid config file path")? {
-            parent_path if parent_path != Path::new("") => {
-                fs::create_dir_all(parent_path)
-                    .with_context(|| format!("failed to create dir {parent_path:?}"))?;
-            },
-            _ => {
-                // Nothing to do if it's a relative path with one component
-            },
-        }
-        let file = File::options()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(path)
-            .with_context(|| format!("failed to open file {path:?} for writing"))?;
-        let mut serializer =
-            serde_json::Serializer::with_formatter(file, PrettyFormatter::with_indent(b"    "));
-        config
-            .serialize(&mut serializer)
-            .with_context(|| format!("failed to write config to file {path:?}"))?;
-        debug!(?path, "saved config");
+        save_created_config(path, config, distro, profile).context("failed to save config")?;
     }
 
-    Ok(config)
+    Ok(())
+}
+
+/// Creates config from command line options, without asking for any input.
+///
+/// Options that are not given are auto-detected if possible, or set to the
+/// same defaults as the interactive config.
+///
+/// Also returns the distro that a custom color alignment is saved for, see
+/// [`Config::set_color_align`].
+#[tracing::instrument(level = "debug", skip(options))]
+fn create_config_non_interactive(
+    options: &Options,
+    backend: Backend,
+) -> Result<(Config, Option<Distro>)> {
+    let custom_presets = read_custom_presets(&options.config_file)
+        .context("failed to read custom presets from config")?;
+
+    let preset = match options.preset.as_deref() {
+        Some(name) => AnyPreset::from_name(name, &custom_presets)
+            .with_context(|| format!("failed to find preset {name:?}"))?,
+        None => Preset::Rainbow.into(),
+    };
+
+    let mode = match options.mode {
+        Some(mode) => mode,
+        None => detect_color_mode().unwrap_or(AnsiMode::Ansi256),
+    };
+
+    let light_dark = match options.theme {
+        Some(theme) => theme,
+        None => detect_background_color()
+            .context("failed to detect background color")?
+            .map(|bg| bg.theme())
+            .unwrap_or(TerminalTheme::Dark),
+    };
+
+    // Check the distro now, so that a typo fails here instead of on every run;
+    // custom alignments also need to know the distro they are made for
+    let distro_variant = if options.distro.is_some()
+        || matches!(options.color_align, Some(ColorAlignment::Custom { .. }))
+    {
+        let (_, distro_variant) = get_distro_ascii(options.distro.as_ref(), backend)
+            .context("failed to get distro ascii")?;
+        distro_variant
+    } else {
+        None
+    };
+
+    let mut config = Config {
+        version: CONFIG_VERSION,
+        preset,
+        mode,
+        light_dark,
+        lightness: options.lightness,
+        color_align: ColorAlignment::Horizontal,
+        distro_color_align: IndexMap::new(),
+        blend: options.blend.unwrap_or_default(),
+        style: options.style.unwrap_or_default(),
+        backend,
+        args: options.args.clone(),
+        distro: options.distro.clone(),
+        pride_month_disable: false,
+        custom_presets,
+        profiles: IndexMap::new(),
+        extra: IndexMap::new(),
+    };
+    if let Some(color_align) = &options.color_align {
+        config.set_color_align(color_align.clone(), distro_variant.as_ref());
+    }
+    debug!(?config, "created config");
+
+    Ok((config, distro_variant))
+}
+
+/// Detects the terminal background color.
+///
+/// Returns `None` if stdout is not a terminal, or if the terminal does not
+/// support querying the background color.
+fn detect_background_color() -> Result<Option<Srgb<u8>>> {
+    if !io::stdout().is_terminal() {
+        return Ok(None);
+    }
+
+    let det_bg = match background_color(QueryOptions::default()) {
+        Ok(bg) => Some(Srgb::<u16>::new(bg.r, bg.g, bg.b).into_format::<u8>()),
+        Err(terminal_colorsaurus::Error::UnsupportedTerminal) => None,
+        Err(err) => {
+            return Err(err).context("failed to get terminal background color");
+        },
+    };
+    debug!(?det_bg, "detected background color");
+
+    Ok(det_bg)
+}
+
+/// Detects the color mode supported by the terminal.
+fn detect_color_mode() -> Option<AnsiMode> {
+    let det_ansi = supports_color::on(supports_color::Stream::Stdout).and_then(|color_level| {
+        if color_level.has_16m {
+            Some(AnsiMode::Rgb)
+        } else if color_level.has_256 {
+            Some(AnsiMode::Ansi256)
+        } else if color_level.has_basic {
+            Some(AnsiMode::Ansi16)
+        } else {
+            None
+        }
+    });
+    debug!(?det_ansi, "detected color mode");
+
+    det_ansi
 }
 
 fn init_tracing_subsriber(debug_mode: bool) -> Result<()> {
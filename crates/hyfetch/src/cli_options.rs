@@ -5,36 +5,89 @@ use std::str::FromStr as _;
 use anyhow::Context as _;
 #[cfg(feature = "autocomplete")]
 use bpaf::ShellComp;
-use bpaf::{construct, long, OptionParser, Parser as _};
+use bpaf::{construct, long, OptionParser, Parser};
 use directories::BaseDirs;
 use itertools::Itertools as _;
-use strum::{VariantArray, VariantNames};
+use strum::VariantNames;
 
 use crate::color_util::{color, Lightness};
+#[cfg(feature = "autocomplete")]
+use crate::models::read_custom_presets;
+use crate::neofetch_util::ColorAlignment;
+use crate::pixel_flag::FlagSize;
+#[cfg(feature = "autocomplete")]
+use crate::presets::AnyPreset;
 use crate::presets::Preset;
-use crate::types::{AnsiMode, Backend};
+use crate::types::{
+    AnsiMode, Backend, Blend, ImageCharset, ListFormat, LogoStyle, OutputFormat, TerminalTheme,
+};
 
 #[derive(Clone, Debug)]
 pub struct Options {
     pub config: bool,
+    pub non_interactive: bool,
     pub config_file: PathBuf,
-    pub preset: Option<Preset>,
+    pub profile: Option<String>,
+    pub preset: Option<String>,
     pub mode: Option<AnsiMode>,
     pub backend: Option<Backend>,
     pub args: Option<Vec<String>>,
     pub scale: Option<f32>,
     pub lightness: Option<Lightness>,
+    pub theme: Option<TerminalTheme>,
+    pub color_align: Option<ColorAlignment>,
+    pub blend: Option<Blend>,
+    pub style: Option<LogoStyle>,
     pub june: bool,
     pub debug: bool,
     pub distro: Option<String>,
-    pub ascii_file: Option<PathBuf>,
+    pub refresh: bool,
+    pub logo: Option<Logo>,
+    pub output: Option<OutputFormat>,
+    pub output_file: Option<PathBuf>,
     pub print_font_logo: bool,
+    pub print_config: bool,
     pub test_print: bool,
     pub ask_exit: bool,
+    pub command: Option<Command>,
+}
+
+/// Ascii art used instead of the distro logo.
+///
+/// Only set from the command line; the config has no key for it.
+#[derive(Clone, Debug)]
+pub enum Logo {
+    AsciiFile(PathBuf),
+    Image {
+        path: PathBuf,
+        charset: Option<ImageCharset>,
+    },
+    PixelFlag(FlagSize),
+}
+
+#[derive(Clone, Debug)]
+pub enum Command {
+    List(ListCommand),
+    Preview(PreviewCommand),
+}
+
+#[derive(Clone, Debug)]
+pub enum ListCommand {
+    Presets { format: ListFormat },
+    Distros { format: ListFormat },
+}
+
+#[derive(Clone, Debug)]
+pub struct PreviewCommand {
+    pub presets: Option<Vec<String>>,
+    pub color_align: Option<ColorAlignment>,
 }
 
 pub fn options() -> OptionParser<Options> {
     let config = long("config").short('c').help("Configure hyfetch").switch();
+    let non_interactive = long("non-interactive")
+        .help("Create the config from the other options instead of asking (use with --config)")
+        .switch();
     let config_file = long("config-file")
         .short('C')
         .help("Use another config file")
@@ -42,19 +95,17 @@ pub fn options() -> OptionParser<Options> {
     #[cfg(feature = "autocomplete")]
     let config_file = config_file.complete_shell(ShellComp::Nothing);
     let config_file = config_file
-        .fallback_with(|| {
-            Ok::<_, anyhow::Error>(
-                BaseDirs::new()
-                    .context("failed to get base dirs")?
-                    .config_dir()
-                    .join("hyfetch.json"),
-            )
-        })
+        .fallback_with(default_config_file)
         .debug_fallback();
+    let profile = long("profile")
+        .help("Use a named profile from the config file (with --config, create or edit it)")
+        .argument::<String>("PROFILE")
+        .optional();
     let preset = long("preset")
         .short('p')
         .help(&*format!(
-            "Use preset
+            "Use preset or a custom preset defined in the config file; combine presets with `+` to \
+             split the ascii art between them, e.g. `transgender+lesbian`
 PRESET={{{presets}}}",
             presets = <Preset as VariantNames>::VARIANTS
                 .iter()
@@ -64,30 +115,7 @@ PRESET={{{presets}}}",
         .argument::<String>("PRESET");
     #[cfg(feature = "autocomplete")]
     let preset = preset.complete(complete_preset);
-    let preset = preset
-        .parse(|s| {
-            Preset::from_str(&s)
-                .or_else(|e| {
-                    if s == "random" {
-                        let mut rng = fastrand::Rng::new();
-                        Ok(*rng
-                            .choice(<Preset as VariantArray>::VARIANTS)
-                            .expect("preset iterator should not be empty"))
-                    } else {
-                        Err(e)
-                    }
-                })
-                .with_context(|| {
-                    format!(
-                        "PRESET should be one of {{{presets}}}",
-                        presets = <Preset as VariantNames>::VARIANTS
-                            .iter()
-                            .chain(iter::once(&"random"))
-                            .join(",")
-                    )
-                })
-        })
-        .optional();
+    let preset = preset.optional();
     let mode = long("mode")
         .short('m')
         .help(&*format!(
@@ -138,9 +166,75 @@ BACKEND={{{backends}}}",
         .argument("SCALE")
         .optional();
     let lightness = long("c-set-l")
+        .long("lightness")
         .help("Set lightness value of the colors")
         .argument("LIGHTNESS")
         .optional();
+    let theme = long("theme")
+        .help(&*format!(
+            "Terminal theme
+THEME={{{themes}}}",
+            themes = TerminalTheme::VARIANTS.join(",")
+        ))
+        .argument::<String>("THEME");
+    #[cfg(feature = "autocomplete")]
+    let theme = theme.complete(complete_theme);
+    let theme = theme
+        .parse(|s| {
+            TerminalTheme::from_str(&s).with_context(|| {
+                format!(
+                    "THEME should be one of {{{themes}}}",
+                    themes = TerminalTheme::VARIANTS.join(",")
+                )
+            })
+        })
+        .optional();
+    let color_align = long("color-align")
+        .help(
+            "Color alignment
+COLOR_ALIGN={horizontal,vertical,diagonal[:ANGLE],radial[:X,Y],custom:SLOT=INDEX,...}",
+        )
+        .argument::<String>("COLOR_ALIGN")
+        .parse(|s| ColorAlignment::from_str(&s))
+        .optional();
+    let blend = long("blend")
+        .help(&*format!(
+            "How colors are blended when spread across the ascii art
+BLEND={{{blends}}}",
+            blends = Blend::VARIANTS.join(",")
+        ))
+        .argument::<String>("BLEND");
+    #[cfg(feature = "autocomplete")]
+    let blend = blend.complete(complete_blend);
+    let blend = blend
+        .parse(|s| {
+            Blend::from_str(&s).with_context(|| {
+                format!(
+                    "BLEND should be one of {{{blends}}}",
+                    blends = Blend::VARIANTS.join(",")
+                )
+            })
+        })
+        .optional();
+    let style = long("style")
+        .help(&*format!(
+            "Whether the logo is drawn with foreground colors, background colors, or both
+STYLE={{{styles}}}",
+            styles = LogoStyle::VARIANTS.join(",")
+        ))
+        .argument::<String>("STYLE");
+    #[cfg(feature = "autocomplete")]
+    let style = style.complete(complete_style);
+    let style = style
+        .parse(|s| {
+            LogoStyle::from_str(&s).with_context(|| {
+                format!(
+                    "STYLE should be one of {{{styles}}}",
+                    styles = LogoStyle::VARIANTS.join(",")
+                )
+            })
+        })
+        .optional();
     let june = long("june").help("Show pride month easter egg").switch();
     let debug = long("debug").help("Debug mode").switch();
     let distro = long("distro")
@@ -152,15 +246,82 @@ BACKEND={{{backends}}}",
         .argument("DISTRO")
         .optional();
     let distro = construct!([distro, test_distro]);
+    let refresh = long("refresh")
+        .help("Detect the distro again, instead of using the cached result")
+        .switch();
     let ascii_file = long("ascii-file")
         .help("Use a specific file for the ascii art")
         .argument("ASCII_FILE");
     #[cfg(feature = "autocomplete")]
     let ascii_file = ascii_file.complete_shell(ShellComp::Nothing);
-    let ascii_file = ascii_file.optional();
+    let ascii_file = construct!(Logo::AsciiFile(ascii_file));
+    let path = long("logo-image")
+        .help("Draw the ascii art from a PNG or PPM image")
+        .argument("IMAGE");
+    #[cfg(feature = "autocomplete")]
+    let path = path.complete_shell(ShellComp::Nothing);
+    let charset = long("logo-charset")
+        .help(&*format!(
+            "Characters used to draw the image given by --logo-image
+CHARSET={{{charsets}}}",
+            charsets = ImageCharset::VARIANTS.join(",")
+        ))
+        .argument::<String>("CHARSET");
+    #[cfg(feature = "autocomplete")]
+    let charset = charset.complete(complete_logo_charset);
+    let charset = charset
+        .parse(|s| {
+            ImageCharset::from_str(&s).with_context(|| {
+                format!(
+                    "CHARSET should be one of {{{charsets}}}",
+                    charsets = ImageCharset::VARIANTS.join(",")
+                )
+            })
+        })
+        .optional();
+    let logo_image = construct!(Logo::Image { path, charset });
+    let pixel_flag = long("pixel-flag")
+        .help(
+            "Draw the preset as a half-block flag instead of the logo (CLI only, not in the config)
+SIZE=COLUMNSxROWS, e.g. 32x8",
+        )
+        .argument::<String>("SIZE")
+        .parse(|s| FlagSize::from_str(&s));
+    let pixel_flag = construct!(Logo::PixelFlag(pixel_flag));
+    let logo = construct!([ascii_file, logo_image, pixel_flag]).optional();
+    let output = long("output")
+        .help(&*format!(
+            "Export the output as an image or web page instead of printing it
+OUTPUT={{{formats}}}",
+            formats = OutputFormat::VARIANTS.join(",")
+        ))
+        .argument::<String>("OUTPUT");
+    #[cfg(feature = "autocomplete")]
+    let output = output.complete(complete_output);
+    let output = output
+        .parse(|s| {
+            OutputFormat::from_str(&s).with_context(|| {
+                format!(
+                    "OUTPUT should be one of {{{formats}}}",
+                    formats = OutputFormat::VARIANTS.join(",")
+                )
+            })
+        })
+        .optional();
+    let output_file = long("output-file")
+        .help(
+            "Write the exported output, or the output of a subcommand, to a file instead of stdout",
+        )
+        .argument("OUTPUT_FILE");
+    #[cfg(feature = "autocomplete")]
+    let output_file = output_file.complete_shell(ShellComp::Nothing);
+    let output_file = output_file.optional();
     let print_font_logo = long("print-font-logo")
         .help("Print the Font Logo / Nerd Font icon of your distro and exit")
         .switch();
+    let print_config = long("print-config")
+        .help("Print the effective config, with the source of each value, and exit")
+        .switch();
     // hidden
     let test_print = long("test-print")
         .help("Print the ascii distro and exit")
@@ -170,25 +331,51 @@ BACKEND={{{backends}}}",
         .help("Ask for input before exiting")
         .switch()
         .hide();
+    let command = {
+        let list = list_command();
+        let preview = preview_command();
+        construct!([list, preview]).optional()
+    };
 
     construct!(Options {
         config,
+        non_interactive,
         config_file,
+        profile,
         preset,
         mode,
         backend,
         args,
         scale,
         lightness,
+        theme,
+        color_align,
+        blend,
+        style,
         june,
         debug,
         distro,
-        ascii_file,
+        refresh,
+        logo,
+        output,
+        output_file,
         print_font_logo,
+        print_config,
         // hidden
         test_print,
         ask_exit,
+        command,
     })
+    .guard(
+        |options| options.config || !options.non_interactive,
+        "--non-interactive should be used with --config",
+    )
+    .guard(
+        |options| {
+            options.output_file.is_none() || options.output.is_some() || options.command.is_some()
+        },
+        "--output-file should be used with --output or a subcommand",
+    )
     .to_options()
     .header(
         &*color(
@@ -200,11 +387,116 @@ BACKEND={{{backends}}}",
     .version(env!("CARGO_PKG_VERSION"))
 }
 
+fn list_command() -> impl Parser<Command> {
+    let presets = {
+        let format = list_format();
+        construct!(ListCommand::Presets { format })
+            .to_options()
+            .descr("List presets with their colors")
+            .command("presets")
+    };
+    let distros = {
+        let format = list_format();
+        construct!(ListCommand::Distros { format })
+            .to_options()
+            .descr("List distros with their match patterns and ascii art metadata")
+            .command("distros")
+    };
+    let list = construct!([presets, distros]);
+    construct!(Command::List(list))
+        .to_options()
+        .descr("List available presets or distros")
+        .command("list")
+}
+
+fn preview_command() -> impl Parser<Command> {
+    let presets = long("presets")
+        .help("Comma-separated presets to show (defaults to all presets)")
+        .argument::<String>("PRESETS")
+        .map(|s| s.split(',').map(|name| name.trim().to_owned()).collect())
+        .optional();
+    let color_align = long("align")
+        .help(
+            "Color alignment (defaults to the configured one)
+ALIGN={horizontal,vertical,diagonal[:ANGLE],radial[:X,Y],custom:SLOT=INDEX,...}",
+        )
+        .argument::<String>("ALIGN")
+        .parse(|s| ColorAlignment::from_str(&s))
+        .optional();
+    let preview = construct!(PreviewCommand {
+        presets,
+        color_align
+    });
+    construct!(Command::Preview(preview))
+        .to_options()
+        .descr("Show the distro ascii art recolored with each preset, without running a backend")
+        .footer("Use with --distro, and with --output and --output-file to save the preview")
+        .command("preview")
+}
+
+fn list_format() -> impl Parser<ListFormat> {
+    let format = long("format")
+        .help(&*format!(
+            "Output format
+FORMAT={{{formats}}}",
+            formats = ListFormat::VARIANTS.join(",")
+        ))
+        .argument::<String>("FORMAT");
+    #[cfg(feature = "autocomplete")]
+    let format = format.complete(complete_list_format);
+    format
+        .parse(|s| {
+            ListFormat::from_str(&s).with_context(|| {
+                format!(
+                    "FORMAT should be one of {{{formats}}}",
+                    formats = ListFormat::VARIANTS.join(",")
+                )
+            })
+        })
+        .fallback(ListFormat::default())
+}
+
+/// Gets the default path of the config file.
+///
+/// `hyfetch.json` is preferred, but an existing `hyfetch.toml` is used if
+/// there is no `hyfetch.json`.
+pub fn default_config_file() -> anyhow::Result<PathBuf> {
+    let config_dir = BaseDirs::new()
+        .context("failed to get base dirs")?
+        .config_dir()
+        .to_owned();
+    let json_path = config_dir.join("hyfetch.json");
+    let toml_path = config_dir.join("hyfetch.toml");
+    if !json_path.is_file() && toml_path.is_file() {
+        return Ok(toml_path);
+    }
+    Ok(json_path)
+}
+
 #[cfg(feature = "autocomplete")]
 fn complete_preset(input: &String) -> Vec<(String, Option<String>)> {
-    <Preset as VariantNames>::VARIANTS
+    // Only the default config file is considered, as other options are not
+    // available during completion
+    let custom_presets = default_config_file()
+        .and_then(read_custom_presets)
+        .unwrap_or_default();
+    AnyPreset::all(&custom_presets)
+        .map(|preset| preset.as_ref().to_owned())
+        .chain(iter::once("random".to_owned()))
+        .filter_map(|name| {
+            if name.starts_with(input) {
+                Some((name, None))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>()
+}
+
+#[cfg(feature = "autocomplete")]
+fn complete_mode(input: &String) -> Vec<(String, Option<String>)> {
+    AnsiMode::VARIANTS
         .iter()
-        .chain(iter::once(&"random"))
         .filter_map(|&name| {
             if name.starts_with(input) {
                 Some((name.to_owned(), None))
@@ -216,8 +508,78 @@ fn complete_preset(input: &String) -> Vec<(String, Option<String>)> {
 }
 
 #[cfg(feature = "autocomplete")]
-fn complete_mode(input: &String) -> Vec<(String, Option<String>)> {
-    AnsiMode::VARIANTS
+fn complete_theme(input: &String) -> Vec<(String, Option<String>)> {
+    TerminalTheme::VARIANTS
+        .iter()
+        .filter_map(|&name| {
+            if name.starts_with(input) {
+                Some((name.to_owned(), None))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>()
+}
+
+#[cfg(feature = "autocomplete")]
+fn complete_blend(input: &String) -> Vec<(String, Option<String>)> {
+    Blend::VARIANTS
+        .iter()
+        .filter_map(|&name| {
+            if name.starts_with(input) {
+                Some((name.to_owned(), None))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>()
+}
+
+#[cfg(feature = "autocomplete")]
+fn complete_style(input: &String) -> Vec<(String, Option<String>)> {
+    LogoStyle::VARIANTS
+        .iter()
+        .filter_map(|&name| {
+            if name.starts_with(input) {
+                Some((name.to_owned(), None))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>()
+}
+
+#[cfg(feature = "autocomplete")]
+fn complete_logo_charset(input: &String) -> Vec<(String, Option<String>)> {
+    ImageCharset::VARIANTS
+        .iter()
+        .filter_map(|&name| {
+            if name.starts_with(input) {
+                Some((name.to_owned(), None))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>()
+}
+
+#[cfg(feature = "autocomplete")]
+fn complete_output(input: &String) -> Vec<(String, Option<String>)> {
+    OutputFormat::VARIANTS
+        .iter()
+        .filter_map(|&name| {
+            if name.starts_with(input) {
+                Some((name.to_owned(), None))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>()
+}
+
+#[cfg(feature = "autocomplete")]
+fn complete_list_format(input: &String) -> Vec<(String, Option<String>)> {
+    ListFormat::VARIANTS
         .iter()
         .filter_map(|&name| {
             if name.starts_with(input) {
@@ -251,4 +613,50 @@ mod tests {
     fn check_options() {
         options().check_invariants(false)
     }
+
+    #[test]
+    fn non_interactive_requires_config() {
+        assert!(options().run_inner(&["--non-interactive"]).is_err());
+        assert!(options()
+            .run_inner(&["--config", "--non-interactive"])
+            .is_ok());
+    }
+
+    #[test]
+    fn output_file_requires_output() {
+        assert!(options().run_inner(&["--output-file", "out.svg"]).is_err());
+        assert!(options()
+            .run_inner(&["--output", "svg", "--output-file", "out.svg"])
+            .is_ok());
+        assert!(options()
+            .run_inner(&["--output-file", "presets.txt", "list", "presets"])
+            .is_ok());
+    }
+
+    #[test]
+    fn logo_options_conflict() {
+        assert!(options()
+            .run_inner(&["--ascii-file", "logo.txt", "--logo-image", "logo.png"])
+            .is_err());
+        assert!(options()
+            .run_inner(&["--ascii-file", "logo.txt", "--logo-charset", "braille"])
+            .is_err());
+        assert!(options()
+            .run_inner(&["--pixel-flag", "32x8", "--ascii-file", "logo.txt"])
+            .is_err());
+        assert!(options()
+            .run_inner(&["--logo-image", "logo.png", "--pixel-flag", "32x8"])
+            .is_err());
+        assert!(options().run_inner(&["--logo-charset", "braille"]).is_err());
+        let options = options()
+            .run_inner(&["--logo-charset", "braille", "--logo-image", "logo.png"])
+            .unwrap();
+        assert!(matches!(
+            options.logo,
+            Some(Logo::Image {
+                charset: Some(ImageCharset::Braille),
+                ..
+            })
+        ));
+    }
 }
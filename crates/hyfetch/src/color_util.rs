@@ -60,8 +60,32 @@ const ANSI256_GRAYSCALE_COLORS: [u8; 30] = [
     245, 246, 247, 248, 249, 250, 251, 252, 253, 254, 255,
 ];
 
+/// The 16 base ANSI colors, as rendered by xterm by default.
+///
+/// See <https://en.wikipedia.org/wiki/ANSI_escape_code#3-bit_and_4-bit>
+const ANSI16_COLORS: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [205, 0, 0],
+    [0, 205, 0],
+    [205, 205, 0],
+    [0, 0, 238],
+    [205, 0, 205],
+    [0, 205, 205],
+    [229, 229, 229],
+    [127, 127, 127],
+    [255, 0, 0],
+    [0, 255, 0],
+    [255, 255, 0],
+    [92, 92, 255],
+    [255, 0, 255],
+    [0, 255, 255],
+    [255, 255, 255],
+];
+
 static MINECRAFT_COLORS_AC: OnceLock<(AhoCorasick, Box<[&str; 30]>)> = OnceLock::new();
+static MINECRAFT_COLORS_ANSI16: OnceLock<[String; 30]> = OnceLock::new();
 static RGB_COLORS_AC: OnceLock<AhoCorasick> = OnceLock::new();
+static ANSI16_LAB_COLORS: OnceLock<[Lab; 16]> = OnceLock::new();
 
 /// Represents the lightness component in [`Okhsl`].
 ///
@@ -238,7 +262,8 @@ impl ToAnsiString for Srgb<u8> {
                 format!("\x1b[{c};5;{indexed}m")
             },
             AnsiMode::Ansi16 => {
-                unimplemented!();
+                let indexed = ansi16_from_rgb(*self);
+                ansi16_escape_code(indexed, foreground_background)
             },
         }
     }
@@ -265,7 +290,8 @@ impl ToAnsiString for SrgbLuma<u8> {
                 format!("\x1b[{c};5;{indexed}m")
             },
             AnsiMode::Ansi16 => {
-                unimplemented!();
+                let rgb_u8_color = Srgb::new(self.luma, self.luma, self.luma);
+                rgb_u8_color.to_ansi_string(mode, foreground_background)
             },
         }
     }
@@ -329,7 +355,29 @@ where
                 ),
             )
         });
-        ac.replace_all(msg, &escape_codes[..])
+        if mode == AnsiMode::Ansi16 {
+            // Minecraft color codes map to the 16 base colors, but they are written as 8-bit
+            // escape codes, which are not supported by every 16-color terminal
+            let escape_codes = MINECRAFT_COLORS_ANSI16.get_or_init(|| {
+                escape_codes.map(|code| {
+                    match code
+                        .strip_prefix("\x1b[38;5;")
+                        .and_then(|code| code.strip_suffix('m'))
+                    {
+                        Some(indexed) => ansi16_escape_code(
+                            indexed
+                                .parse()
+                                .expect("minecraft color index should be valid"),
+                            ForegroundBackground::Foreground,
+                        ),
+                        None => code.to_owned(),
+                    }
+                })
+            });
+            ac.replace_all(msg, &escape_codes[..])
+        } else {
+            ac.replace_all(msg, &escape_codes[..])
+        }
     };
 
     let ac = RGB_COLORS_AC.get_or_init(|| AhoCorasick::new(RGB_COLOR_PATTERNS).unwrap());
@@ -403,6 +451,35 @@ where
     Ok(dst)
 }
 
+/// Finds the index of the perceptually nearest of the 16 base ANSI colors.
+fn ansi16_from_rgb(rgb_u8_color: Srgb<u8>) -> u8 {
+    let lab_f32_colors = ANSI16_LAB_COLORS
+        .get_or_init(|| ANSI16_COLORS.map(|rgb| Srgb::<u8>::from(rgb).into_linear().into_color()));
+    let self_lab_f32: Lab = rgb_u8_color.into_linear().into_color();
+
+    let (indexed, _) = lab_f32_colors
+        .iter()
+        .map(|lab_f32_color| lab_f32_color.improved_difference(self_lab_f32))
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .expect("`ANSI16_COLORS` should not be empty");
+    indexed
+        .try_into()
+        .expect("16-color index should fit in `u8`")
+}
+
+/// Gets the escape code of one of the 16 base ANSI colors.
+fn ansi16_escape_code(indexed: u8, foreground_background: ForegroundBackground) -> String {
+    let c: u8 = match (foreground_background, indexed) {
+        (ForegroundBackground::Foreground, 0..=7) => 30,
+        (ForegroundBackground::Foreground, _) => 90,
+        (ForegroundBackground::Background, 0..=7) => 40,
+        (ForegroundBackground::Background, _) => 100,
+    };
+    let c = c.checked_add(indexed.rem_euclid(8)).unwrap();
+    format!("\x1b[{c}m")
+}
+
 /// Prints with color.
 pub fn printc<S>(msg: S, mode: AnsiMode) -> Result<()>
 where
@@ -431,3 +508,19 @@ pub fn clear_screen(title: Option<&str>, mode: AnsiMode, debug_mode: bool) -> Re
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ansi16_nearest_color() {
+        assert_eq!(ansi16_from_rgb(Srgb::new(0, 0, 0)), 0);
+        assert_eq!(ansi16_from_rgb(Srgb::new(255, 255, 255)), 15);
+        assert_eq!(ansi16_from_rgb(Srgb::new(250, 10, 10)), 9);
+        assert_eq!(
+            Srgb::new(0, 0, 230).to_ansi_string(AnsiMode::Ansi16, ForegroundBackground::Background),
+            "\x1b[44m"
+        );
+    }
+}
@@ -0,0 +1,774 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::{env, fmt};
+
+use anyhow::{anyhow, Context as _, Result};
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use strum::VariantNames;
+use tracing::debug;
+
+use crate::cli_options::Options;
+use crate::color_util::Lightness;
+use crate::models::{
+    load_config_value, read_config_value, to_json_value, Config, Profile, ProfileRule,
+    CONFIG_VERSION,
+};
+use crate::native::hostname;
+use crate::neofetch_util::{default_backend, ColorAlignment};
+use crate::types::{AnsiMode, Backend, Blend, LogoStyle, TerminalTheme};
+
+/// Config keys that can be set with `HYFETCH_*` environment variables.
+const ENV_KEYS: [&str; 11] = [
+    "preset",
+    "mode",
+    "light_dark",
+    "lightness",
+    "color_align",
+    "blend",
+    "style",
+    "backend",
+    "args",
+    "distro",
+    "pride_month_disable",
+];
+
+/// File names of the project-local config, looked up in the current directory
+/// and its ancestors.
+const PROJECT_CONFIG_FILE_NAMES: [&str; 2] = [".hyfetch.json", ".hyfetch.toml"];
+
+/// Config keys that make hyfetch run other programs.
+///
+/// These are ignored in the project-local config, including in its profiles,
+/// since that file comes with whatever directory hyfetch is run in, e.g. a
+/// cloned repo.
+const UNTRUSTED_KEYS: [&str; 2] = ["backend", "args"];
+
+/// Environment variable for selecting a profile by name.
+const PROFILE_ENV_VAR: &str = "HYFETCH_PROFILE";
+
+/// Environment variables that indicate an SSH session.
+const SSH_ENV_VARS: [&str; 3] = ["SSH_CONNECTION", "SSH_CLIENT", "SSH_TTY"];
+
+/// Where a config value came from.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum ConfigSource {
+    Default,
+    System(PathBuf),
+    User(PathBuf),
+    Project(PathBuf),
+    Profile(String),
+    Env,
+    Cli,
+}
+
+/// Resolved config, along with the source of each of its values.
+#[derive(Clone, Debug)]
+pub struct LayeredConfig {
+    pub config: Config,
+    pub sources: IndexMap<String, ConfigSource>,
+}
+
+/// Config layers, from lowest to highest priority:
+///
+/// 1. built-in defaults
+/// 2. system-wide config (e.g. `/etc/hyfetch.json`)
+/// 3. user config
+/// 4. project-local config (`.hyfetch.json` in the current directory or an
+///    ancestor), except for the settings that run other programs
+/// 5. profile from the config files, selected by `--profile`,
+///    `HYFETCH_PROFILE` or its `when` rule
+/// 6. `HYFETCH_*` environment variables
+/// 7. command line options
+#[derive(Clone, Debug)]
+pub struct ConfigLayers {
+    layers: Vec<(ConfigSource, Map<String, Value>)>,
+    /// Profile selected by name
+    profile: Option<String>,
+    /// Whether to select a profile by its `when` rule, if none is selected by
+    /// name
+    auto_profile: bool,
+}
+
+/// Environment that profile rules are matched against.
+#[derive(Clone, Debug)]
+struct ProfileEnv {
+    hostname: Option<String>,
+    term: Option<String>,
+    ssh: bool,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::System(path) => write!(f, "system config {path}", path = path.display()),
+            Self::User(path) => write!(f, "user config {path}", path = path.display()),
+            Self::Project(path) => write!(f, "project config {path}", path = path.display()),
+            Self::Profile(name) => write!(f, "profile {name}"),
+            Self::Env => write!(f, "environment"),
+            Self::Cli => write!(f, "command line"),
+        }
+    }
+}
+
+impl ConfigLayers {
+    /// Loads all config layers.
+    ///
+    /// The user config is read from `options.config_file`, and is upgraded in
+    /// place if it was written by an older version.
+    #[tracing::instrument(level = "debug", skip(options))]
+    pub fn load(options: &Options) -> Result<Self> {
+        let mut layers = vec![(ConfigSource::Default, default_layer())];
+
+        // Only the user config can be left over from the Python implementation,
+        // so the other config files are read as partial layers, without filling
+        // in the defaults of old configs
+        if let Some(path) = system_config_file() {
+            if let Some(value) = read_config_value(&path)
+                .with_context(|| format!("failed to load system config {path:?}"))?
+            {
+                layers.push((ConfigSource::System(path), into_object(value, "system")?));
+            }
+        }
+
+        let path = &options.config_file;
+        if let Some(value) = load_config_value(path, true)
+            .with_context(|| format!("failed to load user config {path:?}"))?
+        {
+            layers.push((
+                ConfigSource::User(path.clone()),
+                into_object(value, "user")?,
+            ));
+        }
+
+        let project_path = project_config_file().context("failed to find project config")?;
+        if let Some(path) = project_path.filter(|path| path != &options.config_file) {
+            if let Some(value) = read_config_value(&path)
+                .with_context(|| format!("failed to load project config {path:?}"))?
+            {
+                let layer = project_layer(value, &path)?;
+                layers.push((ConfigSource::Project(path), layer));
+            }
+        }
+
+        layers.push((
+            ConfigSource::Env,
+            env_layer().context("failed to read environment")?,
+        ));
+        layers.push((
+            ConfigSource::Cli,
+            cli_layer(options).context("failed to read options")?,
+        ));
+
+        let profile = match options.profile.clone() {
+            Some(profile) => Some(profile),
+            None => match env::var(PROFILE_ENV_VAR) {
+                Ok(profile) => Some(profile),
+                Err(env::VarError::NotPresent) => None,
+                Err(err) => {
+                    return Err(err).with_context(|| format!("failed to read {PROFILE_ENV_VAR}"));
+                },
+            },
+        };
+
+        let layers = Self {
+            layers,
+            profile,
+            auto_profile: true,
+        };
+        debug!(?layers, "config layers");
+        Ok(layers)
+    }
+
+    /// Replaces the user config, e.g. after it is created interactively.
+    ///
+    /// No profile is applied afterwards, since the created config already has
+    /// the values chosen for it.
+    pub fn set_user_config(&mut self, path: &Path, config: &Config) -> Result<()> {
+        let value = to_json_value(config)?;
+        let layer = (
+            ConfigSource::User(path.to_owned()),
+            into_object(value, "user")?,
+        );
+        self.layers
+            .retain(|(source, _)| !matches!(source, ConfigSource::User(_)));
+        let i = self
+            .layers
+            .iter()
+            .position(|(source, _)| {
+                !matches!(source, ConfigSource::Default | ConfigSource::System(_))
+            })
+            .unwrap_or(self.layers.len());
+        self.layers.insert(i, layer);
+        self.profile = None;
+        self.auto_profile = false;
+        Ok(())
+    }
+
+    /// Merges the layers into the effective config.
+    ///
+    /// Returns `None` if there is no user config, and the other layers don't
+    /// specify a preset and color mode.
+    pub fn resolve(&self) -> Result<Option<LayeredConfig>> {
+        let mut merged = Map::new();
+        let mut sources = IndexMap::new();
+        let mut profile_applied = false;
+        for (source, layer) in &self.layers {
+            // The profile goes on top of the config files
+            if !profile_applied && matches!(source, ConfigSource::Env | ConfigSource::Cli) {
+                profile_applied = true;
+                if let Some((name, profile)) = self.select_profile(&merged)? {
+                    debug!(name, ?profile, "selected profile");
+                    merge_layer(
+                        &mut merged,
+                        &mut sources,
+                        &ConfigSource::Profile(name),
+                        &profile.values,
+                    );
+                }
+            }
+            merge_layer(&mut merged, &mut sources, source, layer);
+        }
+
+        // Looking for fastfetch takes a while, so only do it when needed
+        if !merged.contains_key("backend") {
+            merged.insert("backend".to_owned(), to_json_value(&default_backend())?);
+            sources.insert("backend".to_owned(), ConfigSource::Default);
+        }
+
+        let has_user_config = self
+            .layers
+            .iter()
+            .any(|(source, _)| matches!(source, ConfigSource::User(_)));
+        let has_required = merged.contains_key("preset") && merged.contains_key("mode");
+        if !has_user_config && !has_required {
+            return Ok(None);
+        }
+
+        let config: Config = serde_path_to_error::deserialize(Value::Object(merged))
+            .context("failed to parse merged config")?;
+        debug!(?config, ?sources, "resolved config");
+
+        Ok(Some(LayeredConfig { config, sources }))
+    }
+
+    /// Selects the profile by name, or else the first one whose rule matches.
+    fn select_profile(&self, merged: &Map<String, Value>) -> Result<Option<(String, Profile)>> {
+        let mut profiles: IndexMap<String, Profile> = match merged.get("profiles") {
+            Some(profiles) => {
+                serde_path_to_error::deserialize(profiles).context("failed to parse profiles")?
+            },
+            None => IndexMap::new(),
+        };
+
+        if let Some(name) = &self.profile {
+            let profile = profiles.shift_remove(name).with_context(|| {
+                format!(
+                    "profile {name:?} should be one of {{{profiles}}}",
+                    profiles = profiles
+                        .keys()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                        .join(",")
+                )
+            })?;
+            return Ok(Some((name.clone(), profile)));
+        }
+
+        if !self.auto_profile {
+            return Ok(None);
+        }
+        let env = ProfileEnv::detect();
+        debug!(?env, "profile environment");
+        Ok(profiles
+            .into_iter()
+            .find(|(_, profile)| profile.when.as_ref().is_some_and(|rule| env.matches(rule))))
+    }
+}
+
+impl ProfileEnv {
+    fn detect() -> Self {
+        Self {
+            hostname: hostname(),
+            term: env::var("TERM").ok(),
+            ssh: SSH_ENV_VARS
+                .iter()
+                .any(|var| env::var_os(var).is_some_and(|v| !v.is_empty())),
+        }
+    }
+
+    fn matches(&self, rule: &ProfileRule) -> bool {
+        let glob = |pattern: &Option<String>, value: &Option<String>| match (pattern, value) {
+            (None, _) => true,
+            (Some(pattern), Some(value)) => glob_match(
+                &pattern.to_lowercase().chars().collect::<Vec<_>>(),
+                &value.to_lowercase().chars().collect::<Vec<_>>(),
+            ),
+            (Some(_), None) => false,
+        };
+        glob(&rule.hostname, &self.hostname)
+            && glob(&rule.term, &self.term)
+            && rule.ssh.map_or(true, |ssh| ssh == self.ssh)
+    }
+}
+
+impl LayeredConfig {
+    /// Formats the effective config values, each with the layer it came from.
+    pub fn to_lines(&self) -> Result<Vec<String>> {
+        let Value::Object(obj) = to_json_value(&self.config)? else {
+            unreachable!("config should serialize to a JSON object");
+        };
+
+        let entries: Vec<(String, String, String)> = obj
+            .into_iter()
+            .map(|(key, value)| {
+                let source = match self.sources.get(&key) {
+                    Some(ConfigSource::Env) => format!("environment {var}", var = env_var(&key)),
+                    Some(source) => source.to_string(),
+                    None => ConfigSource::Default.to_string(),
+                };
+                (key, value.to_string(), source)
+            })
+            .collect();
+        let key_w = entries.iter().map(|(k, ..)| k.len()).max().unwrap_or(0);
+        let value_w = entries.iter().map(|(_, v, _)| v.len()).max().unwrap_or(0);
+
+        Ok(entries
+            .into_iter()
+            .map(|(key, value, source)| format!("{key:key_w$}  {value:value_w$}  ({source})"))
+            .collect())
+    }
+}
+
+/// Gets the path of the system-wide config file, if any.
+pub fn system_config_file() -> Option<PathBuf> {
+    #[cfg(unix)]
+    let dir = Some(PathBuf::from("/etc"));
+    #[cfg(windows)]
+    let dir = env::var_os("ProgramData").map(|dir| PathBuf::from(dir).join("hyfetch"));
+    #[cfg(not(any(unix, windows)))]
+    let dir: Option<PathBuf> = None;
+
+    let dir = dir?;
+    let json_path = dir.join("hyfetch.json");
+    let toml_path = dir.join("hyfetch.toml");
+    if !json_path.is_file() && toml_path.is_file() {
+        Some(toml_path)
+    } else {
+        Some(json_path)
+    }
+}
+
+/// Finds the project-local config file in the current directory or its
+/// ancestors.
+pub fn project_config_file() -> Result<Option<PathBuf>> {
+    let current_dir = env::current_dir().context("failed to get current dir")?;
+    Ok(current_dir.ancestors().find_map(|dir| {
+        PROJECT_CONFIG_FILE_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+    }))
+}
+
+fn env_var(key: &str) -> String {
+    format!("HYFETCH_{key}", key = key.to_uppercase())
+}
+
+/// Merges a layer into the config, recording where each value came from.
+fn merge_layer(
+    merged: &mut Map<String, Value>,
+    sources: &mut IndexMap<String, ConfigSource>,
+    source: &ConfigSource,
+    layer: &Map<String, Value>,
+) {
+    for (key, value) in layer {
+        match (merged.get_mut(key), value) {
+            // Custom presets, profiles and per-distro color alignments from every
+            // layer are available, by name
+            (Some(Value::Object(merged_value)), Value::Object(value))
+                if key == "custom_presets" || key == "profiles" || key == "distro_color_align" =>
+            {
+                merged_value.extend(value.clone());
+            },
+            _ => {
+                merged.insert(key.clone(), value.clone());
+            },
+        }
+        sources.insert(key.clone(), source.clone());
+    }
+}
+
+/// Matches a glob pattern, where `*` matches any characters and `?` matches
+/// one character.
+fn glob_match(pattern: &[char], s: &[char]) -> bool {
+    match (pattern.split_first(), s.split_first()) {
+        (Some(('*', rest)), _) => {
+            glob_match(rest, s) || s.split_first().is_some_and(|(_, s)| glob_match(pattern, s))
+        },
+        (Some(('?', rest)), Some((_, s))) => glob_match(rest, s),
+        (Some((p, rest)), Some((c, s))) if p == c => glob_match(rest, s),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn into_object(value: Value, layer: &str) -> Result<Map<String, Value>> {
+    match value {
+        Value::Object(obj) => Ok(obj),
+        _ => Err(anyhow!("{layer} config should be a JSON object")),
+    }
+}
+
+/// Reads the project-local config, without the [`UNTRUSTED_KEYS`].
+fn project_layer(value: Value, path: &Path) -> Result<Map<String, Value>> {
+    let mut layer = into_object(value, "project")?;
+    let profiles = match layer.get_mut("profiles") {
+        Some(Value::Object(profiles)) => profiles.values_mut().collect(),
+        _ => Vec::new(),
+    };
+    for profile in profiles {
+        if let Value::Object(profile) = profile {
+            remove_untrusted_keys(profile, path);
+        }
+    }
+    remove_untrusted_keys(&mut layer, path);
+    Ok(layer)
+}
+
+fn remove_untrusted_keys(obj: &mut Map<String, Value>, path: &Path) {
+    for key in UNTRUSTED_KEYS {
+        if let Some(value) = obj.remove(key) {
+            debug!(
+                key,
+                ?value,
+                ?path,
+                "ignoring untrusted key in project config"
+            );
+        }
+    }
+}
+
+/// Built-in defaults, except for the backend, which is only detected when no
+/// layer sets it.
+fn default_layer() -> Map<String, Value> {
+    let Value::Object(obj) = json!({
+        "version": CONFIG_VERSION,
+        "light_dark": TerminalTheme::Dark,
+        "lightness": null,
+        "color_align": ColorAlignment::Horizontal,
+        "blend": Blend::default(),
+        "style": LogoStyle::default(),
+        "args": null,
+        "distro": null,
+        "pride_month_disable": false,
+    }) else {
+        unreachable!("defaults should be a JSON object");
+    };
+    obj
+}
+
+fn env_layer() -> Result<Map<String, Value>> {
+    let mut layer = Map::new();
+    for key in ENV_KEYS {
+        let var = env_var(key);
+        let s = match env::var(&var) {
+            Ok(s) => s,
+            Err(env::VarError::NotPresent) => continue,
+            Err(err) => return Err(err).with_context(|| format!("failed to read {var}")),
+        };
+        let value = parse_env_value(key, &s).with_context(|| format!("invalid {var}={s:?}"))?;
+        layer.insert(key.to_owned(), value);
+    }
+    Ok(layer)
+}
+
+fn parse_env_value(key: &str, s: &str) -> Result<Value> {
+    let value = match key {
+        "preset" | "args" | "distro" => Value::String(s.to_owned()),
+        "mode" => parse_variant::<AnsiMode>(s)?,
+        "light_dark" => parse_variant::<TerminalTheme>(s)?,
+        "lightness" => serde_json::to_value(Lightness::new(s.parse()?)?)?,
+        "color_align" => serde_json::to_value(ColorAlignment::from_str(s)?)?,
+        "blend" => parse_variant::<Blend>(s)?,
+        "style" => parse_variant::<LogoStyle>(s)?,
+        "backend" => parse_variant::<Backend>(s)?,
+        "pride_month_disable" => match s {
+            "1" | "true" => Value::Bool(true),
+            "0" | "false" => Value::Bool(false),
+            _ => return Err(anyhow!("should be one of {{true,false,1,0}}")),
+        },
+        _ => unreachable!("{key:?} should be in `ENV_KEYS`"),
+    };
+    Ok(value)
+}
+
+fn parse_variant<T>(s: &str) -> Result<Value>
+where
+    T: FromStr + VariantNames + Serialize,
+{
+    let value = T::from_str(s).map_err(|_| {
+        anyhow!(
+            "should be one of {{{variants}}}",
+            variants = T::VARIANTS.join(",")
+        )
+    })?;
+    Ok(serde_json::to_value(value)?)
+}
+
+fn cli_layer(options: &Options) -> Result<Map<String, Value>> {
+    let mut layer = Map::new();
+    let mut insert = |key: &str, value: Option<Value>| {
+        if let Some(value) = value {
+            layer.insert(key.to_owned(), value);
+        }
+    };
+    insert("preset", options.preset.clone().map(Value::String));
+    insert("mode", options.mode.map(serde_json::to_value).transpose()?);
+    insert(
+        "light_dark",
+        options.theme.map(serde_json::to_value).transpose()?,
+    );
+    insert(
+        "lightness",
+        options.lightness.map(serde_json::to_value).transpose()?,
+    );
+    insert(
+        "color_align",
+        options
+            .color_align
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()?,
+    );
+    insert(
+        "blend",
+        options.blend.map(serde_json::to_value).transpose()?,
+    );
+    insert(
+        "style",
+        options.style.map(serde_json::to_value).transpose()?,
+    );
+    insert(
+        "backend",
+        options.backend.map(serde_json::to_value).transpose()?,
+    );
+    insert("args", options.args.clone().map(Into::into));
+    insert("distro", options.distro.clone().map(Value::String));
+    Ok(layer)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn glob_match_wildcards() {
+        let glob = |pattern: &str, s: &str| {
+            glob_match(
+                &pattern.chars().collect::<Vec<_>>(),
+                &s.chars().collect::<Vec<_>>(),
+            )
+        };
+        assert!(glob("*.example.com", "build01.example.com"));
+        assert!(glob("build??", "build01"));
+        assert!(glob("xterm*", "xterm"));
+        assert!(!glob("build??", "build1"));
+        assert!(!glob("*.example.com", "example.com"));
+    }
+
+    fn layer(value: Value) -> Map<String, Value> {
+        into_object(value, "test").unwrap()
+    }
+
+    fn layers(layers: Vec<(ConfigSource, Map<String, Value>)>) -> ConfigLayers {
+        ConfigLayers {
+            layers: [(ConfigSource::Default, default_layer())]
+                .into_iter()
+                .chain(layers)
+                .collect(),
+            profile: None,
+            auto_profile: false,
+        }
+    }
+
+    #[test]
+    fn resolve_layers_in_order() {
+        let system = ConfigSource::System(PathBuf::from("/etc/hyfetch.json"));
+        let user = ConfigSource::User(PathBuf::from("hyfetch.json"));
+        let project = ConfigSource::Project(PathBuf::from(".hyfetch.toml"));
+        let layers = layers(vec![
+            (
+                system.clone(),
+                layer(json!({
+                    "preset": "rainbow",
+                    "mode": "8bit",
+                    "light_dark": "light",
+                    "blend": "oklab",
+                    "distro_color_align": { "Arch": { "mode": "vertical" } },
+                })),
+            ),
+            (
+                user.clone(),
+                layer(json!({
+                    "preset": "transgender",
+                    "mode": "rgb",
+                    "light_dark": "dark",
+                    "distro_color_align": { "Fedora": { "mode": "vertical" } },
+                })),
+            ),
+            (
+                project.clone(),
+                layer(json!({ "preset": "lesbian", "mode": "ansi" })),
+            ),
+            (
+                ConfigSource::Env,
+                layer(json!({ "preset": "bisexual", "backend": "native" })),
+            ),
+            (ConfigSource::Cli, layer(json!({ "preset": "nonbinary" }))),
+        ]);
+
+        let LayeredConfig { config, sources } = layers.resolve().unwrap().unwrap();
+        assert_eq!(config.preset.as_ref(), "nonbinary");
+        assert_eq!(sources["preset"], ConfigSource::Cli);
+        assert_eq!(config.backend, Backend::Native);
+        assert_eq!(sources["backend"], ConfigSource::Env);
+        assert_eq!(config.mode, AnsiMode::Ansi16);
+        assert_eq!(sources["mode"], project);
+        assert_eq!(config.light_dark, TerminalTheme::Dark);
+        assert_eq!(sources["light_dark"], user);
+        assert_eq!(config.blend, Blend::Oklab);
+        assert_eq!(sources["blend"], system);
+        assert_eq!(config.style, LogoStyle::default());
+        assert_eq!(sources["style"], ConfigSource::Default);
+        // Maps of named entries are merged across layers
+        assert_eq!(
+            config.distro_color_align.keys().collect::<Vec<_>>(),
+            ["Arch", "Fedora"]
+        );
+
+        let lines = LayeredConfig { config, sources }.to_lines().unwrap();
+        let line = |key: &str| {
+            lines
+                .iter()
+                .find(|line| line.starts_with(&format!("{key} ")))
+                .unwrap()
+        };
+        assert!(line("preset").ends_with("(command line)"));
+        assert!(line("backend").ends_with("(environment HYFETCH_BACKEND)"));
+        assert!(line("mode").ends_with("(project config .hyfetch.toml)"));
+        assert!(line("style").ends_with("(default)"));
+    }
+
+    #[test]
+    fn project_config_cannot_run_programs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".hyfetch.json");
+        let value = json!({
+            "preset": "lesbian",
+            "backend": "neofetch",
+            "args": "--config /tmp/evil.sh",
+            "profiles": { "ssh": { "when": { "ssh": true }, "args": "--config /tmp/evil.sh" } },
+        });
+        fs::write(&path, value.to_string()).unwrap();
+        let value = read_config_value(&path).unwrap().unwrap();
+
+        let project = project_layer(value, &path).unwrap();
+        assert_eq!(
+            Value::Object(project.clone()),
+            json!({
+                "preset": "lesbian",
+                "profiles": { "ssh": { "when": { "ssh": true } } },
+            })
+        );
+
+        let user = layer(json!({
+            "preset": "rainbow",
+            "mode": "rgb",
+            "backend": "native",
+            "args": "--logo",
+        }));
+        let layers = layers(vec![
+            (ConfigSource::User(PathBuf::from("hyfetch.json")), user),
+            (ConfigSource::Project(path), project),
+        ]);
+        let LayeredConfig { config, .. } = layers.resolve().unwrap().unwrap();
+        assert_eq!(config.preset.as_ref(), "lesbian");
+        assert_eq!(config.backend, Backend::Native);
+        assert_eq!(config.args, Some(vec!["--logo".to_owned()]));
+    }
+
+    #[test]
+    fn resolve_without_config() {
+        assert!(layers(Vec::new()).resolve().unwrap().is_none());
+
+        // Enough values from other layers stand in for the user config
+        let layers = layers(vec![(
+            ConfigSource::Env,
+            layer(json!({ "preset": "rainbow", "mode": "rgb" })),
+        )]);
+        let LayeredConfig { config, sources } = layers.resolve().unwrap().unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(sources["backend"], ConfigSource::Default);
+    }
+
+    #[test]
+    fn merge_layer_records_sources() {
+        let mut merged = Map::new();
+        let mut sources = IndexMap::new();
+        merge_layer(
+            &mut merged,
+            &mut sources,
+            &ConfigSource::Env,
+            &layer(json!({ "preset": "rainbow", "custom_presets": { "a": ["#000000"] } })),
+        );
+        merge_layer(
+            &mut merged,
+            &mut sources,
+            &ConfigSource::Cli,
+            &layer(json!({ "preset": "lesbian", "custom_presets": { "b": ["#FFFFFF"] } })),
+        );
+        assert_eq!(
+            Value::Object(merged),
+            json!({
+                "preset": "lesbian",
+                "custom_presets": { "a": ["#000000"], "b": ["#FFFFFF"] },
+            })
+        );
+        assert_eq!(sources["preset"], ConfigSource::Cli);
+    }
+
+    #[test]
+    fn parse_env_values() {
+        assert_eq!(
+            parse_env_value("backend", "native").unwrap(),
+            json!("native")
+        );
+        assert_eq!(
+            parse_env_value("pride_month_disable", "1").unwrap(),
+            json!(true)
+        );
+        assert_eq!(
+            parse_env_value("color_align", "diagonal:30").unwrap(),
+            json!({ "mode": "diagonal", "angle": 30.0 })
+        );
+        for (key, s) in [
+            ("mode", "12bit"),
+            ("light_dark", "dim"),
+            ("lightness", "2"),
+            ("lightness", "bright"),
+            ("color_align", "sideways"),
+            ("pride_month_disable", "yes"),
+        ] {
+            assert!(parse_env_value(key, s).is_err(), "{key}={s}");
+        }
+        let err = parse_env_value("backend", "bash").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("should be one of {neofetch,fastfetch"));
+    }
+}
@@ -0,0 +1,196 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{Context as _, Result};
+use serde::{Deserialize, Serialize};
+use strum::VariantArray as _;
+use tracing::debug;
+
+use crate::distros::Distro;
+use crate::native;
+use crate::types::Backend;
+use crate::utils::get_cache_path;
+
+/// File name of the distro detection cache, in the cache dir.
+const CACHE_FILE_NAME: &str = "distro.json";
+
+/// What the cached detection result depends on.
+///
+/// The cache is only used if all of these are unchanged.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+struct CacheKey {
+    version: String,
+    backend: Backend,
+    /// Modification time of the os-release file, if there is one
+    os_release_modified: Option<SystemTime>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct CacheEntry {
+    key: CacheKey,
+    /// Distro name as detected by the backend
+    name: String,
+    /// See [`Distro::name`]
+    variant: Option<String>,
+}
+
+impl CacheKey {
+    fn current(backend: Backend) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            backend,
+            os_release_modified: native::os_release_modified(),
+        }
+    }
+}
+
+/// Gets the cached distro name and variant detected by the backend.
+///
+/// Returns `None` if nothing is cached, or if the cache is outdated.
+pub fn load(backend: Backend) -> Option<(String, Option<Distro>)> {
+    let path = cache_file_path().ok()?;
+    load_file(&path, &CacheKey::current(backend))
+}
+
+/// Caches the distro name and variant detected by the backend.
+pub fn store(backend: Backend, name: &str, variant: Option<&Distro>) -> Result<()> {
+    store_file(
+        &cache_file_path()?,
+        CacheKey::current(backend),
+        name,
+        variant,
+    )
+}
+
+/// Removes the cached distro, so it's detected again on the next run.
+pub fn clear() -> Result<()> {
+    clear_file(&cache_file_path()?)
+}
+
+fn load_file(path: &Path, key: &CacheKey) -> Option<(String, Option<Distro>)> {
+    let entry = match fs::read_to_string(path) {
+        Ok(buf) => buf,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return None,
+        Err(err) => {
+            debug!(?err, ?path, "failed to read distro cache");
+            return None;
+        },
+    };
+    let entry: CacheEntry = match serde_json::from_str(&entry) {
+        Ok(entry) => entry,
+        Err(err) => {
+            debug!(?err, ?path, "failed to parse distro cache");
+            return None;
+        },
+    };
+
+    if entry.key != *key {
+        debug!(cached = ?entry.key, current = ?key, "distro cache is outdated");
+        return None;
+    }
+    let variant = match entry.variant {
+        Some(variant) => Some(
+            Distro::VARIANTS
+                .iter()
+                .find(|distro| distro.name() == variant)
+                .cloned()?,
+        ),
+        None => None,
+    };
+    Some((entry.name, variant))
+}
+
+fn store_file(path: &Path, key: CacheKey, name: &str, variant: Option<&Distro>) -> Result<()> {
+    let entry = CacheEntry {
+        key,
+        name: name.to_owned(),
+        variant: variant.map(|distro| distro.name().to_owned()),
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create cache dir {parent:?}"))?;
+    }
+    let buf = serde_json::to_string(&entry).context("failed to serialize distro cache")?;
+    fs::write(path, buf).with_context(|| format!("failed to write to file {path:?}"))?;
+    Ok(())
+}
+
+fn clear_file(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err).with_context(|| format!("failed to remove file {path:?}")),
+    }
+}
+
+fn cache_file_path() -> Result<PathBuf> {
+    Ok(get_cache_path()
+        .context("failed to get cache path")?
+        .join(CACHE_FILE_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache").join(CACHE_FILE_NAME);
+        let key = CacheKey::current(Backend::Native);
+        let arch = Distro::detect("arch").unwrap();
+
+        assert!(load_file(&path, &key).is_none());
+        store_file(&path, key.clone(), "Arch Linux", Some(&arch)).unwrap();
+        assert_eq!(
+            load_file(&path, &key),
+            Some(("Arch Linux".to_owned(), Some(arch)))
+        );
+        store_file(&path, key.clone(), "Unknown", None).unwrap();
+        assert_eq!(load_file(&path, &key), Some(("Unknown".to_owned(), None)));
+
+        clear_file(&path).unwrap();
+        assert!(load_file(&path, &key).is_none());
+        clear_file(&path).unwrap();
+
+        fs::write(&path, "not json").unwrap();
+        assert!(load_file(&path, &key).is_none());
+    }
+
+    #[test]
+    fn stale_cache_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CACHE_FILE_NAME);
+        let key = CacheKey {
+            version: "2.0.0".to_owned(),
+            backend: Backend::Neofetch,
+            os_release_modified: Some(SystemTime::UNIX_EPOCH),
+        };
+        store_file(&path, key.clone(), "Fedora Linux", None).unwrap();
+        assert!(load_file(&path, &key).is_some());
+
+        let stale = [
+            CacheKey {
+                version: "2.0.1".to_owned(),
+                ..key.clone()
+            },
+            CacheKey {
+                backend: Backend::Fastfetch,
+                ..key.clone()
+            },
+            CacheKey {
+                os_release_modified: Some(SystemTime::now()),
+                ..key.clone()
+            },
+            CacheKey {
+                os_release_modified: None,
+                ..key.clone()
+            },
+        ];
+        for key in stale {
+            assert!(load_file(&path, &key).is_none(), "{key:?}");
+        }
+    }
+}
@@ -1,3 +1,56 @@
 #![allow(non_camel_case_types)]
 
+use strum::VariantArray as _;
+
 include!(concat!(env!("OUT_DIR"), "/distros.rs"));
+
+/// Minimum Jaro-Winkler similarity for a distro pattern to be suggested.
+const MIN_SIMILARITY: f64 = 0.7;
+
+impl Distro {
+    /// Finds distro patterns similar to the name, most similar first, with at
+    /// most one pattern per distro.
+    ///
+    /// Used to suggest a name when [`Self::detect`] has no match, e.g. because
+    /// of a typo. Each suggested name is matched by [`Self::detect`].
+    pub fn similar<S>(name: S) -> Vec<(String, Self)>
+    where
+        S: AsRef<str>,
+    {
+        let name = name.as_ref().to_lowercase();
+        let mut similar: Vec<(f64, String, Self)> = Self::VARIANTS
+            .iter()
+            .filter_map(|distro| {
+                distro
+                    .patterns()
+                    .iter()
+                    .map(|pattern| pattern.replace('*', ""))
+                    .filter(|candidate| Self::detect(candidate).as_ref() == Some(distro))
+                    .map(|candidate| (strsim::jaro_winkler(&name, &candidate), candidate))
+                    .max_by(|(a, _), (b, _)| a.total_cmp(b))
+                    .filter(|&(similarity, _)| similarity >= MIN_SIMILARITY)
+                    .map(|(similarity, candidate)| (similarity, candidate, distro.clone()))
+            })
+            .collect();
+        similar.sort_by(|(a, ..), (b, ..)| b.total_cmp(a));
+        similar
+            .into_iter()
+            .map(|(_, candidate, distro)| (candidate, distro))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn similar_suggests_detectable_names() {
+        let similar = Distro::similar("fedroa");
+        assert_eq!(similar[0].0, "fedora");
+        for (name, distro) in similar {
+            assert_eq!(Distro::detect(name), Some(distro));
+        }
+        assert!(Distro::similar("xyzzyq").is_empty());
+    }
+}
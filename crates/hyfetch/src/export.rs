@@ -0,0 +1,472 @@
+use std::cmp;
+use std::fmt::Write as _;
+
+use ansi_colours::rgb_from_ansi256;
+use palette::Srgb;
+use unicode_segmentation::UnicodeSegmentation as _;
+use unicode_width::UnicodeWidthStr as _;
+
+use crate::types::TerminalTheme;
+
+/// Font size of the exported text, in pixels.
+const FONT_SIZE: f32 = 14.0;
+/// Width of a terminal cell, relative to the font size.
+const CELL_WIDTH: f32 = 0.6;
+/// Height of a terminal cell, relative to the font size.
+const CELL_HEIGHT: f32 = 1.2;
+/// Padding around the exported image, in pixels.
+const PADDING: f32 = 16.0;
+const FONT_FAMILY: &str = "'DejaVu Sans Mono', Menlo, Consolas, monospace";
+/// Largest row or column the cursor can be moved to, so that a stray escape
+/// sequence in the backend output can't make the grid huge.
+const MAX_CURSOR: usize = 1000;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+struct Style {
+    fg: Option<Srgb<u8>>,
+    bg: Option<Srgb<u8>>,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct Cell {
+    text: String,
+    style: Style,
+}
+
+/// A minimal virtual terminal, which replays an ANSI stream into a grid of
+/// cells.
+///
+/// Only the escape sequences used by the *fetch backends are interpreted
+/// (SGR colors and cursor movement); everything else is ignored.
+#[derive(Clone, Debug, Default)]
+struct Screen {
+    rows: Vec<Vec<Cell>>,
+    row: usize,
+    col: usize,
+    style: Style,
+}
+
+impl Screen {
+    fn from_ansi(ansi: &str) -> Self {
+        let mut screen = Self::default();
+        let mut rest = ansi;
+
+        while !rest.is_empty() {
+            let text_end = rest
+                .find(['\x1b', '\n', '\r', '\t', '\x08'])
+                .unwrap_or(rest.len());
+            let (text, tail) = rest.split_at(text_end);
+            for gr in text.graphemes(true) {
+                screen.put(gr);
+            }
+            rest = tail;
+
+            let mut chars = rest.chars();
+            match chars.next() {
+                Some('\n') => {
+                    screen.row = screen.row.saturating_add(1);
+                    screen.col = 0;
+                },
+                Some('\r') => screen.col = 0,
+                Some('\t') => {
+                    screen.col = screen.col.div_euclid(8).saturating_add(1).saturating_mul(8)
+                },
+                Some('\x08') => screen.col = screen.col.saturating_sub(1),
+                Some('\x1b') => match chars.next() {
+                    Some('[') => {
+                        let seq = chars.as_str();
+                        let end = seq.find(|c: char| ('@'..='~').contains(&c));
+                        let Some(end) = end else {
+                            break;
+                        };
+                        let (params, final_byte) = (&seq[..end], &seq[end..end.saturating_add(1)]);
+                        screen.csi(params, final_byte);
+                        chars = seq[end.saturating_add(1)..].chars();
+                    },
+                    Some(']') => {
+                        // Operating system command, terminated by BEL or ST
+                        let seq = chars.as_str();
+                        let end = seq
+                            .find('\x07')
+                            .map(|i| i.saturating_add(1))
+                            .or_else(|| seq.find("\x1b\\").map(|i| i.saturating_add(2)))
+                            .unwrap_or(seq.len());
+                        chars = seq[end..].chars();
+                    },
+                    _ => {},
+                },
+                _ => {},
+            }
+            rest = chars.as_str();
+        }
+
+        // Trim trailing blank rows
+        while screen
+            .rows
+            .last()
+            .is_some_and(|row| row.iter().all(Cell::is_blank))
+        {
+            screen.rows.pop();
+        }
+
+        screen
+    }
+
+    /// Writes a grapheme at the cursor, and moves the cursor past it.
+    ///
+    /// Wide characters take up two cells, the second of which is left empty.
+    fn put(&mut self, gr: &str) {
+        let width = gr.width();
+        if width == 0 {
+            return;
+        }
+        if self.rows.len() <= self.row {
+            self.rows.resize_with(self.row.saturating_add(1), Vec::new);
+        }
+        let row = &mut self.rows[self.row];
+        let end = self.col.saturating_add(width);
+        if row.len() < end {
+            row.resize_with(end, Cell::default);
+        }
+        row[self.col] = Cell {
+            text: gr.to_owned(),
+            style: self.style,
+        };
+        for cell in &mut row[self.col.saturating_add(1)..end] {
+            *cell = Cell {
+                text: String::new(),
+                style: self.style,
+            };
+        }
+        self.col = end;
+    }
+
+    fn csi(&mut self, params: &str, final_byte: &str) {
+        if params.starts_with(['?', '>', '<', '=']) {
+            // Private mode sequences (e.g. hiding the cursor)
+            return;
+        }
+        let nums: Vec<usize> = params.split(';').map(|p| p.parse().unwrap_or(0)).collect();
+        let n = nums.first().copied().filter(|&n| n > 0).unwrap_or(1);
+
+        match final_byte {
+            "m" => self.sgr(&nums),
+            "A" => self.row = self.row.saturating_sub(n),
+            "B" | "E" => {
+                self.row = self.row.saturating_add(n);
+                if final_byte == "E" {
+                    self.col = 0;
+                }
+            },
+            "F" => {
+                self.row = self.row.saturating_sub(n);
+                self.col = 0;
+            },
+            "C" => self.col = self.col.saturating_add(n),
+            "D" => self.col = self.col.saturating_sub(n),
+            "G" => self.col = n.saturating_sub(1),
+            "H" | "f" => {
+                self.row = n.saturating_sub(1);
+                self.col = nums
+                    .get(1)
+                    .copied()
+                    .filter(|&n| n > 0)
+                    .unwrap_or(1)
+                    .saturating_sub(1);
+            },
+            "K" => {
+                if let Some(row) = self.rows.get_mut(self.row) {
+                    match nums.first() {
+                        Some(1) => row
+                            .iter_mut()
+                            .take(self.col.saturating_add(1))
+                            .for_each(|c| *c = Cell::default()),
+                        Some(2) => row.clear(),
+                        _ => row.truncate(self.col),
+                    }
+                }
+            },
+            "J" if nums.first() == Some(&2) => self.rows.clear(),
+            _ => {},
+        }
+        self.row = cmp::min(self.row, MAX_CURSOR);
+        self.col = cmp::min(self.col, MAX_CURSOR);
+    }
+
+    fn sgr(&mut self, nums: &[usize]) {
+        let mut nums = nums.iter().copied();
+        while let Some(n) = nums.next() {
+            match n {
+                0 => self.style = Style::default(),
+                1 => self.style.bold = true,
+                3 => self.style.italic = true,
+                4 => self.style.underline = true,
+                22 => self.style.bold = false,
+                23 => self.style.italic = false,
+                24 => self.style.underline = false,
+                30..=37 => self.style.fg = Some(indexed_color(n.saturating_sub(30))),
+                40..=47 => self.style.bg = Some(indexed_color(n.saturating_sub(40))),
+                90..=97 => self.style.fg = Some(indexed_color(n.saturating_sub(82))),
+                100..=107 => self.style.bg = Some(indexed_color(n.saturating_sub(92))),
+                38 | 48 => {
+                    let color = match nums.next() {
+                        Some(5) => nums.next().map(indexed_color),
+                        Some(2) => match (nums.next(), nums.next(), nums.next()) {
+                            (Some(r), Some(g), Some(b)) => Some(Srgb::new(
+                                u8::try_from(r).unwrap_or(u8::MAX),
+                                u8::try_from(g).unwrap_or(u8::MAX),
+                                u8::try_from(b).unwrap_or(u8::MAX),
+                            )),
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+                    if n == 38 {
+                        self.style.fg = color;
+                    } else {
+                        self.style.bg = color;
+                    }
+                },
+                39 => self.style.fg = None,
+                49 => self.style.bg = None,
+                _ => {},
+            }
+        }
+    }
+
+    /// Groups each row into runs of cells with the same style.
+    fn runs(&self) -> impl Iterator<Item = Vec<(usize, Style, String)>> + '_ {
+        self.rows.iter().map(|row| {
+            let mut runs: Vec<(usize, Style, String)> = Vec::new();
+            for (col, cell) in row.iter().enumerate() {
+                match runs.last_mut() {
+                    Some((_, style, text)) if *style == cell.style => text.push_str(&cell.text),
+                    _ => runs.push((col, cell.style, cell.text.clone())),
+                }
+            }
+            // Drop trailing whitespace without background
+            if let Some((_, style, text)) = runs.last_mut() {
+                if style.bg.is_none() {
+                    text.truncate(text.trim_end().len());
+                }
+            }
+            runs.retain(|(_, _, text)| !text.is_empty());
+            runs
+        })
+    }
+
+    fn width(&self) -> usize {
+        self.rows.iter().map(Vec::len).max().unwrap_or(0)
+    }
+}
+
+impl Cell {
+    fn is_blank(&self) -> bool {
+        self.text.trim().is_empty() && self.style.bg.is_none()
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            text: " ".to_owned(),
+            style: Style::default(),
+        }
+    }
+}
+
+/// Converts a colored ANSI stream into a standalone HTML document.
+pub fn to_html(ansi: &str, theme: TerminalTheme) -> String {
+    let screen = Screen::from_ansi(ansi);
+    let (fg, bg) = default_colors(theme);
+
+    let mut buf = String::new();
+    write!(
+        buf,
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>hyfetch</title>
+<style>
+body {{ margin: 0; background: {bg}; }}
+pre {{ margin: 0; padding: {PADDING}px; color: {fg}; font-family: {FONT_FAMILY}; font-size: {FONT_SIZE}px; line-height: {CELL_HEIGHT}; }}
+</style>
+</head>
+<body>
+<pre>"#,
+        fg = hex(fg),
+        bg = hex(bg),
+    )
+    .unwrap();
+
+    for runs in screen.runs() {
+        let mut col = 0;
+        for (start, style, text) in runs {
+            buf.push_str(&" ".repeat(start.saturating_sub(col)));
+            col = start.saturating_add(text.width());
+            let css = css(style);
+            if css.is_empty() {
+                buf.push_str(&escape(&text));
+            } else {
+                write!(
+                    buf,
+                    r#"<span style="{css}">{text}</span>"#,
+                    text = escape(&text)
+                )
+                .unwrap();
+            }
+        }
+        buf.push('\n');
+    }
+
+    buf.push_str("</pre>\n</body>\n</html>\n");
+    buf
+}
+
+/// Converts a colored ANSI stream into a standalone SVG image.
+pub fn to_svg(ansi: &str, theme: TerminalTheme) -> String {
+    let screen = Screen::from_ansi(ansi);
+    let (fg, bg) = default_colors(theme);
+    let cell_w = FONT_SIZE * CELL_WIDTH;
+    let cell_h = FONT_SIZE * CELL_HEIGHT;
+    let width = screen.width() as f32 * cell_w + PADDING * 2.0;
+    let height = screen.rows.len() as f32 * cell_h + PADDING * 2.0;
+
+    let mut buf = String::new();
+    write!(
+        buf,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width:.1}" height="{height:.1}" viewBox="0 0 {width:.1} {height:.1}">
+<rect width="100%" height="100%" fill="{bg}"/>
+<g font-family="{FONT_FAMILY}" font-size="{FONT_SIZE}" fill="{fg}" xml:space="preserve">
+"#,
+        fg = hex(fg),
+        bg = hex(bg),
+    )
+    .unwrap();
+
+    for (i, runs) in screen.runs().enumerate() {
+        let y = PADDING + i as f32 * cell_h;
+        let baseline = y + FONT_SIZE;
+        for (start, style, text) in runs {
+            let x = PADDING + start as f32 * cell_w;
+            if let Some(bg) = style.bg {
+                writeln!(
+                    buf,
+                    r#"<rect x="{x:.1}" y="{y:.1}" width="{w:.1}" height="{cell_h:.1}" fill="{bg}"/>"#,
+                    w = text.width() as f32 * cell_w,
+                    bg = hex(bg),
+                )
+                .unwrap();
+            }
+            if text.trim().is_empty() {
+                continue;
+            }
+            let mut attrs = String::new();
+            if let Some(fg) = style.fg {
+                write!(attrs, r#" fill="{fg}""#, fg = hex(fg)).unwrap();
+            }
+            if style.bold {
+                attrs.push_str(r#" font-weight="bold""#);
+            }
+            if style.italic {
+                attrs.push_str(r#" font-style="italic""#);
+            }
+            if style.underline {
+                attrs.push_str(r#" text-decoration="underline""#);
+            }
+            writeln!(
+                buf,
+                r#"<text x="{x:.1}" y="{baseline:.1}"{attrs}>{text}</text>"#,
+                text = escape(&text)
+            )
+            .unwrap();
+        }
+    }
+
+    buf.push_str("</g>\n</svg>\n");
+    buf
+}
+
+fn default_colors(theme: TerminalTheme) -> (Srgb<u8>, Srgb<u8>) {
+    match theme {
+        TerminalTheme::Light => (Srgb::new(0x1e, 0x1e, 0x1e), Srgb::new(0xff, 0xff, 0xff)),
+        TerminalTheme::Dark => (Srgb::new(0xe5, 0xe5, 0xe5), Srgb::new(0x1e, 0x1e, 0x1e)),
+    }
+}
+
+fn indexed_color(indexed: usize) -> Srgb<u8> {
+    rgb_from_ansi256(u8::try_from(indexed).unwrap_or(u8::MAX)).into()
+}
+
+fn hex(rgb: Srgb<u8>) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb.red, rgb.green, rgb.blue)
+}
+
+fn css(style: Style) -> String {
+    let mut css = Vec::new();
+    if let Some(fg) = style.fg {
+        css.push(format!("color: {fg}", fg = hex(fg)));
+    }
+    if let Some(bg) = style.bg {
+        css.push(format!("background-color: {bg}", bg = hex(bg)));
+    }
+    if style.bold {
+        css.push("font-weight: bold".to_owned());
+    }
+    if style.italic {
+        css.push("font-style: italic".to_owned());
+    }
+    if style.underline {
+        css.push("text-decoration: underline".to_owned());
+    }
+    css.join("; ")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn screen_cursor_movement() {
+        // Ascii art first, then info text moved up beside it, like neofetch does
+        let screen = Screen::from_ansi("\x1b[31mab\x1b[0m\ncd\n\x1b[2A\x1b[4Cinfo\n\x1b[4Cmore");
+        let rows: Vec<String> = screen
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|c| &*c.text).collect())
+            .collect();
+        assert_eq!(rows, ["ab  info", "cd  more"]);
+        assert_eq!(screen.rows[0][0].style.fg, Some(indexed_color(1)));
+        assert_eq!(screen.rows[1][0].style.fg, None);
+    }
+
+    #[test]
+    fn screen_wide_characters() {
+        let screen = Screen::from_ansi("日本x\n\x1b[4Cy");
+        let texts: Vec<&str> = screen.rows[0].iter().map(|c| &*c.text).collect();
+        assert_eq!(texts, ["日", "", "本", "", "x"]);
+        assert_eq!(screen.rows[1].len(), 5);
+
+        // The second line lines up with the display width of the first
+        let html = to_html("日本x\n\x1b[4Cy", TerminalTheme::Dark);
+        assert!(html.contains("<pre>日本x\n    y\n</pre>"));
+    }
+
+    #[test]
+    fn screen_cursor_is_capped() {
+        let screen = Screen::from_ansi("\x1b[99999999;99999999Hx\x1b[99999999Cy");
+        assert_eq!(screen.rows.len(), MAX_CURSOR.checked_add(1).unwrap());
+        assert_eq!(screen.width(), MAX_CURSOR.checked_add(1).unwrap());
+    }
+}
@@ -0,0 +1,583 @@
+use std::cmp;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, ensure, Context as _, Result};
+use itertools::Itertools as _;
+use palette::{FromColor as _, Oklab, Srgb};
+use tracing::debug;
+
+use crate::ascii::RawAsciiArt;
+use crate::types::ImageCharset;
+
+/// Width of the ascii art made from an image, in terminal columns.
+pub const DEFAULT_WIDTH: u16 = 40;
+
+/// Number of color slots available in ascii art (`${c1}` to `${c6}`).
+const MAX_SLOTS: usize = 6;
+
+/// Squared distance in Oklab below which two colors share a color slot.
+const MIN_SLOT_DISTANCE: f32 = 0.002;
+
+/// Maximum number of k-means iterations when clustering colors into slots.
+const CLUSTER_ITERATIONS: usize = 16;
+
+/// Bits of the braille dots in a cell, in row-major order.
+///
+/// See https://en.wikipedia.org/wiki/Braille_Patterns#Block
+const BRAILLE_DOTS: [u32; 8] = [0x01, 0x08, 0x02, 0x10, 0x04, 0x20, 0x40, 0x80];
+
+/// Characters of increasing density, from dark to light.
+const RAMP: [char; 9] = ['.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Image with 8-bit RGBA pixels in row-major order.
+#[derive(Clone, Debug)]
+pub struct RgbaImage {
+    pub w: usize,
+    pub h: usize,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+impl RgbaImage {
+    /// Reads a PNG or PPM image.
+    pub fn open<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let bytes = fs::read(path).with_context(|| format!("failed to read image {path:?}"))?;
+        if bytes.starts_with(PNG_SIGNATURE) {
+            Self::from_png(&bytes).with_context(|| format!("failed to decode png {path:?}"))
+        } else if bytes.starts_with(b"P3") || bytes.starts_with(b"P6") {
+            Self::from_ppm(&bytes).with_context(|| format!("failed to decode ppm {path:?}"))
+        } else {
+            Err(anyhow!("{path:?} should be a PNG or PPM image"))
+        }
+    }
+
+    pub fn from_png(bytes: &[u8]) -> Result<Self> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info().context("failed to read png header")?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut buf)
+            .context("failed to read png frame")?;
+        let buf = &buf[..info.buffer_size()];
+
+        let pixels = match info.color_type {
+            png::ColorType::Grayscale => buf.iter().map(|&l| [l, l, l, u8::MAX]).collect(),
+            png::ColorType::GrayscaleAlpha => buf
+                .chunks_exact(2)
+                .map(|p| [p[0], p[0], p[0], p[1]])
+                .collect(),
+            png::ColorType::Rgb => buf
+                .chunks_exact(3)
+                .map(|p| [p[0], p[1], p[2], u8::MAX])
+                .collect(),
+            png::ColorType::Rgba => buf
+                .chunks_exact(4)
+                .map(|p| [p[0], p[1], p[2], p[3]])
+                .collect(),
+            png::ColorType::Indexed => {
+                unreachable!("`EXPAND` should have converted indexed colors to rgb")
+            },
+        };
+
+        Self::new(
+            usize::try_from(info.width).context("png width should fit in usize")?,
+            usize::try_from(info.height).context("png height should fit in usize")?,
+            pixels,
+        )
+    }
+
+    /// Decodes a binary (`P6`) or plain (`P3`) PPM image.
+    pub fn from_ppm(bytes: &[u8]) -> Result<Self> {
+        let mut tokens = PpmTokens { bytes, pos: 0 };
+        let magic = tokens.next_token().context("failed to read magic number")?;
+        let w = tokens.next_number().context("failed to read width")?;
+        let h = tokens.next_number().context("failed to read height")?;
+        let max = tokens.next_number().context("failed to read max value")?;
+        ensure!(
+            (1..=u16::MAX.into()).contains(&max),
+            "max value should be between 1 and {max}",
+            max = u16::MAX
+        );
+        let len = w
+            .checked_mul(h)
+            .and_then(|n| n.checked_mul(3))
+            .context("image size should not overflow")?;
+
+        let samples: Vec<usize> = match magic {
+            b"P3" => (0..len)
+                .map(|_| tokens.next_number())
+                .collect::<Result<_>>()
+                .context("failed to read pixels")?,
+            b"P6" => {
+                // A single whitespace separates the header from the pixels
+                let data = bytes
+                    .get(tokens.pos.checked_add(1).unwrap()..)
+                    .context("missing pixels")?;
+                if max < 256 {
+                    data.iter().take(len).map(|&v| v.into()).collect()
+                } else {
+                    data.chunks_exact(2)
+                        .take(len)
+                        .map(|v| u16::from_be_bytes([v[0], v[1]]).into())
+                        .collect()
+                }
+            },
+            _ => bail!("unsupported ppm magic number {magic:?}"),
+        };
+        ensure!(
+            samples.len() == len,
+            "expected {len} samples, found {found}",
+            found = samples.len()
+        );
+
+        let pixels = samples
+            .chunks_exact(3)
+            .map(|p| {
+                let [r, g, b] = [p[0], p[1], p[2]]
+                    .map(|v| (cmp::min(v, max) as f32 / max as f32 * 255.0).round() as u8);
+                [r, g, b, u8::MAX]
+            })
+            .collect();
+        Self::new(w, h, pixels)
+    }
+
+    fn new(w: usize, h: usize, pixels: Vec<[u8; 4]>) -> Result<Self> {
+        ensure!(
+            Some(pixels.len()) == w.checked_mul(h),
+            "expected {w}x{h} pixels, found {len}",
+            len = pixels.len()
+        );
+        Ok(Self { w, h, pixels })
+    }
+
+    /// Draws the image as ascii art at most `width` columns wide.
+    ///
+    /// Transparent parts of the image are left blank. The colors of the other
+    /// parts are clustered into color slots, numbered from the top left, so
+    /// that presets and color alignments apply as with any distro logo.
+    pub fn to_ascii(&self, charset: ImageCharset, width: u16) -> Result<RawAsciiArt> {
+        let img = self.cropped().context("image should have opaque pixels")?;
+        let (cell_w, cell_h) = cell_size(charset);
+        let cols = cmp::min(usize::from(width), img.w.div_ceil(cell_w));
+        ensure!(cols > 0, "width should not be 0");
+        let scale = img.w as f32 / cols.checked_mul(cell_w).unwrap() as f32;
+        let rows = ((img.h as f32 / scale).ceil() as usize).div_ceil(cell_h);
+        debug!(cols, rows, scale, "draw image as ascii");
+
+        // Draw each cell, keeping its color for the color slots
+        let cells: Vec<Vec<Option<(char, Oklab)>>> = (0..rows)
+            .map(|row| {
+                (0..cols)
+                    .map(|col| {
+                        let dots: Vec<Option<Srgb<f32>>> = (0..cell_h)
+                            .cartesian_product(0..cell_w)
+                            .map(|(y, x)| {
+                                img.sample_dot(
+                                    col.checked_mul(cell_w).unwrap().checked_add(x).unwrap(),
+                                    row.checked_mul(cell_h).unwrap().checked_add(y).unwrap(),
+                                    scale,
+                                )
+                            })
+                            .collect();
+                        let color = mean_color(dots.iter().flatten())?;
+                        let c = match charset {
+                            ImageCharset::Braille => {
+                                let bits = dots
+                                    .iter()
+                                    .zip(BRAILLE_DOTS)
+                                    .filter(|(dot, _)| dot.is_some())
+                                    .fold(0, |bits, (_, bit)| bits | bit);
+                                char::from_u32(0x2800 | bits)
+                                    .expect("braille pattern should be a valid char")
+                            },
+                            ImageCharset::HalfBlock => match (dots[0], dots[1]) {
+                                (Some(_), Some(_)) => '█',
+                                (Some(_), None) => '▀',
+                                (None, _) => '▄',
+                            },
+                            ImageCharset::Ramp => {
+                                let last_i = RAMP.len().checked_sub(1).unwrap();
+                                let i = (color.l.clamp(0.0, 1.0) * last_i as f32).round() as usize;
+                                RAMP[cmp::min(i, last_i)]
+                            },
+                        };
+                        Some((c, color))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let colors: Vec<Oklab> = cells.iter().flatten().flatten().map(|&(_, c)| c).collect();
+        let mut slots = cluster_colors(&colors).into_iter();
+
+        let mut asc = String::new();
+        let mut last_slot = 1;
+        for line in cells {
+            let slots: Vec<Option<usize>> = line
+                .iter()
+                .map(|cell| {
+                    cell.map(|_| slots.next().expect("every drawn cell should have a slot"))
+                })
+                .collect();
+            // Each line starts with a color code, so there are no uncolored cells
+            let mut slot = slots.iter().flatten().next().map_or(last_slot, |&s| s);
+            write!(asc, "${{c{slot}}}").unwrap();
+            for (cell, cell_slot) in line.iter().zip(slots) {
+                match (cell, cell_slot) {
+                    (Some((c, _)), Some(s)) => {
+                        if s != slot {
+                            write!(asc, "${{c{s}}}").unwrap();
+                            slot = s;
+                        }
+                        asc.push(*c);
+                    },
+                    _ => asc.push(' '),
+                }
+            }
+            asc.push('\n');
+            last_slot = slot;
+        }
+
+        Ok(RawAsciiArt {
+            asc,
+            fg: Vec::new(),
+        })
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        self.pixels[y.checked_mul(self.w).unwrap().checked_add(x).unwrap()]
+    }
+
+    fn is_opaque(&self, x: usize, y: usize) -> bool {
+        self.pixel(x, y)[3] >= 128
+    }
+
+    /// Crops the image to its opaque pixels.
+    fn cropped(&self) -> Option<Self> {
+        let opaque = (0..self.h)
+            .cartesian_product(0..self.w)
+            .filter(|&(y, x)| self.is_opaque(x, y));
+        let (y0, y1, x0, x1) = opaque.fold(None, |bounds, (y, x)| {
+            let (y0, y1, x0, x1) = bounds.unwrap_or((y, y, x, x));
+            Some((
+                cmp::min(y0, y),
+                cmp::max(y1, y),
+                cmp::min(x0, x),
+                cmp::max(x1, x),
+            ))
+        })?;
+        let pixels = (y0..=y1)
+            .cartesian_product(x0..=x1)
+            .map(|(y, x)| self.pixel(x, y))
+            .collect();
+        Some(Self {
+            w: x1.checked_sub(x0).unwrap().checked_add(1).unwrap(),
+            h: y1.checked_sub(y0).unwrap().checked_add(1).unwrap(),
+            pixels,
+        })
+    }
+
+    /// Gets the color of a dot covering `scale` by `scale` pixels, or `None`
+    /// if the dot is mostly transparent.
+    fn sample_dot(&self, dx: usize, dy: usize, scale: f32) -> Option<Srgb<f32>> {
+        let span = |d: usize, len: usize| {
+            let start = cmp::min((d as f32 * scale) as usize, len);
+            let end = cmp::max(
+                ((d as f32 + 1.0) * scale).ceil() as usize,
+                start.saturating_add(1),
+            );
+            start..cmp::min(end, len)
+        };
+        let (xs, ys) = (span(dx, self.w), span(dy, self.h));
+
+        let (mut n, mut alpha, mut r, mut g, mut b) = (0.0, 0.0, 0.0, 0.0, 0.0);
+        for (y, x) in ys.cartesian_product(xs) {
+            let [pr, pg, pb, pa] = self.pixel(x, y);
+            let a = f32::from(pa) / 255.0;
+            n += 1.0;
+            alpha += a;
+            r += f32::from(pr) * a;
+            g += f32::from(pg) * a;
+            b += f32::from(pb) * a;
+        }
+        if n == 0.0 || alpha / n < 0.5 {
+            return None;
+        }
+        let scale = alpha * 255.0;
+        Some(Srgb::new(r / scale, g / scale, b / scale))
+    }
+}
+
+/// Tokens of a PPM header, separated by whitespace, with `#` comments.
+struct PpmTokens<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PpmTokens<'a> {
+    fn next_token(&mut self) -> Result<&'a [u8]> {
+        loop {
+            match self.bytes.get(self.pos) {
+                Some(b'#') => {
+                    while !matches!(self.bytes.get(self.pos), Some(b'\n') | None) {
+                        self.pos = self.pos.checked_add(1).unwrap();
+                    }
+                },
+                Some(c) if c.is_ascii_whitespace() => {
+                    self.pos = self.pos.checked_add(1).unwrap();
+                },
+                Some(_) => break,
+                None => bail!("unexpected end of file"),
+            }
+        }
+        let start = self.pos;
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|c| !c.is_ascii_whitespace())
+        {
+            self.pos = self.pos.checked_add(1).unwrap();
+        }
+        Ok(&self.bytes[start..self.pos])
+    }
+
+    fn next_number(&mut self) -> Result<usize> {
+        let token = self.next_token()?;
+        std::str::from_utf8(token)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .with_context(|| format!("{token:?} should be a number"))
+    }
+}
+
+/// Gets the number of dots in each cell, horizontally and vertically.
+///
+/// Terminal cells are about twice as tall as they are wide, so dots are
+/// roughly square.
+fn cell_size(charset: ImageCharset) -> (usize, usize) {
+    match charset {
+        ImageCharset::Braille => (2, 4),
+        ImageCharset::HalfBlock | ImageCharset::Ramp => (1, 2),
+    }
+}
+
+fn mean_color<'a, I>(colors: I) -> Option<Oklab>
+where
+    I: IntoIterator<Item = &'a Srgb<f32>>,
+{
+    let [r, g, b] = mean(colors.into_iter().map(|c| [c.red, c.green, c.blue]))?;
+    Some(Oklab::from_color(Srgb::new(r, g, b).into_linear()))
+}
+
+/// Gets the component-wise mean of the values, or `None` if there are none.
+fn mean<I>(values: I) -> Option<[f32; 3]>
+where
+    I: IntoIterator<Item = [f32; 3]>,
+{
+    let (n, sum) = values
+        .into_iter()
+        .fold((0.0, [0.0; 3]), |(n, [s0, s1, s2]), [v0, v1, v2]| {
+            (n + 1.0, [s0 + v0, s1 + v1, s2 + v2])
+        });
+    (n > 0.0).then(|| sum.map(|s| s / n))
+}
+
+/// Clusters colors into at most [`MAX_SLOTS`] color slots with k-means.
+///
+/// Returns the slot of each color, numbered from 1 in order of first
+/// appearance.
+fn cluster_colors(colors: &[Oklab]) -> Vec<usize> {
+    let distance =
+        |a: &Oklab, b: &Oklab| (a.l - b.l).powi(2) + (a.a - b.a).powi(2) + (a.b - b.b).powi(2);
+    let nearest = |c: &Oklab, centroids: &[Oklab]| {
+        centroids
+            .iter()
+            .position_min_by(|a, b| distance(c, a).total_cmp(&distance(c, b)))
+            .expect("there should be at least one centroid")
+    };
+
+    // Start from colors far apart from each other
+    let mut centroids: Vec<Oklab> = colors.first().copied().into_iter().collect();
+    while centroids.len() < MAX_SLOTS {
+        let min_distance = |c: &Oklab| {
+            centroids
+                .iter()
+                .map(|k| distance(c, k))
+                .fold(f32::INFINITY, f32::min)
+        };
+        let farthest = colors
+            .iter()
+            .map(|c| (min_distance(c), c))
+            .max_by(|(a, _), (b, _)| a.total_cmp(b));
+        match farthest {
+            Some((d, &c)) if d >= MIN_SLOT_DISTANCE => centroids.push(c),
+            _ => break,
+        }
+    }
+    if centroids.is_empty() {
+        return Vec::new();
+    }
+
+    let mut labels: Vec<usize> = colors.iter().map(|c| nearest(c, &centroids)).collect();
+    for _ in 0..CLUSTER_ITERATIONS {
+        for (i, centroid) in centroids.iter_mut().enumerate() {
+            let members = colors
+                .iter()
+                .zip(&labels)
+                .filter(|&(_, &label)| label == i)
+                .map(|(c, _)| [c.l, c.a, c.b]);
+            if let Some([l, a, b]) = mean(members) {
+                *centroid = Oklab::new(l, a, b);
+            }
+        }
+        let new_labels: Vec<usize> = colors.iter().map(|c| nearest(c, &centroids)).collect();
+        if new_labels == labels {
+            break;
+        }
+        labels = new_labels;
+    }
+
+    let order: Vec<usize> = labels.iter().copied().unique().collect();
+    labels
+        .iter()
+        .map(|label| {
+            order
+                .iter()
+                .position(|o| o == label)
+                .unwrap()
+                .checked_add(1)
+                .unwrap()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_colors_map_to_slots() {
+        // Red over blue, with a transparent border
+        let (t, r, b) = ([0, 0, 0, 0], [255, 0, 0, 255], [0, 0, 255, 255]);
+        #[rustfmt::skip]
+        let pixels = vec![
+            t, t, t, t,
+            t, r, r, t,
+            t, r, r, t,
+            t, b, b, t,
+            t, b, b, t,
+            t, t, t, t,
+        ];
+        let img = RgbaImage::new(4, 6, pixels).unwrap();
+
+        let asc = img.to_ascii(ImageCharset::HalfBlock, 40).unwrap();
+        assert_eq!(asc.asc, "${c1}██\n${c2}██\n");
+        let asc = img.to_ascii(ImageCharset::Braille, 40).unwrap();
+        assert_eq!(asc.asc, "${c1}⣿\n");
+    }
+
+    #[test]
+    fn decode_ppm() {
+        let (r, b) = ([255, 0, 0, 255], [0, 0, 255, 255]);
+
+        let plain = b"P3\n# red and blue\n2 1\n# max value\n255\n255 0 0  0 0 255\n";
+        assert_eq!(RgbaImage::from_ppm(plain).unwrap().pixels, [r, b]);
+
+        let mut binary = b"P6 2 1 255\n".to_vec();
+        binary.extend([255, 0, 0, 0, 0, 255]);
+        let img = RgbaImage::from_ppm(&binary).unwrap();
+        assert_eq!((img.w, img.h), (2, 1));
+        assert_eq!(img.pixels, [r, b]);
+
+        // Samples are scaled to 8 bits, and are big-endian when 16 bits wide
+        let mut wide = b"P6 1 1 65535\n".to_vec();
+        wide.extend([0xff, 0xff, 0x00, 0x00, 0x80, 0x00]);
+        assert_eq!(
+            RgbaImage::from_ppm(&wide).unwrap().pixels,
+            [[255, 0, 128, 255]]
+        );
+        let plain_wide = b"P3 1 1 1000 1000 0 500";
+        assert_eq!(
+            RgbaImage::from_ppm(plain_wide).unwrap().pixels,
+            [[255, 0, 128, 255]]
+        );
+
+        assert!(RgbaImage::from_ppm(b"P6 2 1 255\n\xff\x00\x00").is_err());
+        assert!(RgbaImage::from_ppm(b"P3 1 1 0 0 0 0").is_err());
+    }
+
+    #[test]
+    fn decode_png() {
+        let encode = |w, h, color_type, bit_depth, palette: Option<Vec<u8>>, data: &[u8]| {
+            let mut buf = Vec::new();
+            let mut encoder = png::Encoder::new(&mut buf, w, h);
+            encoder.set_color(color_type);
+            encoder.set_depth(bit_depth);
+            if let Some(palette) = palette {
+                encoder.set_palette(palette);
+            }
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(data).unwrap();
+            writer.finish().unwrap();
+            buf
+        };
+
+        let rgba = encode(
+            2,
+            1,
+            png::ColorType::Rgba,
+            png::BitDepth::Eight,
+            None,
+            &[255, 0, 0, 255, 0, 0, 255, 0],
+        );
+        let img = RgbaImage::from_png(&rgba).unwrap();
+        assert_eq!((img.w, img.h), (2, 1));
+        assert_eq!(img.pixels, [[255, 0, 0, 255], [0, 0, 255, 0]]);
+
+        let wide = encode(
+            1,
+            1,
+            png::ColorType::Rgb,
+            png::BitDepth::Sixteen,
+            None,
+            &[0xff, 0xff, 0x00, 0x00, 0x80, 0x00],
+        );
+        assert_eq!(
+            RgbaImage::from_png(&wide).unwrap().pixels,
+            [[255, 0, 128, 255]]
+        );
+
+        let indexed = encode(
+            2,
+            1,
+            png::ColorType::Indexed,
+            png::BitDepth::Eight,
+            Some(vec![255, 0, 0, 0, 0, 255]),
+            &[1, 0],
+        );
+        assert_eq!(
+            RgbaImage::from_png(&indexed).unwrap().pixels,
+            [[0, 0, 255, 255], [255, 0, 0, 255]]
+        );
+
+        let gray = encode(
+            1,
+            1,
+            png::ColorType::Grayscale,
+            png::BitDepth::Eight,
+            None,
+            &[7],
+        );
+        assert_eq!(RgbaImage::from_png(&gray).unwrap().pixels, [[7, 7, 7, 255]]);
+
+        assert!(RgbaImage::from_png(&rgba[..rgba.len() / 2]).is_err());
+    }
+}
@@ -1,11 +1,23 @@
 pub mod ascii;
 pub mod cli_options;
 pub mod color_util;
+pub mod config_layers;
+pub mod distro_cache;
 pub mod distros;
+pub mod export;
 pub mod font_logo;
+pub mod image;
+pub mod list;
 pub mod models;
+pub mod native;
 pub mod neofetch_util;
+pub mod pixel_flag;
 pub mod presets;
+pub mod preview;
 pub mod pride_month;
+pub mod renderer;
+pub mod tui;
 pub mod types;
 pub mod utils;
+
+pub use crate::renderer::Renderer;
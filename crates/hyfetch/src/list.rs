@@ -0,0 +1,240 @@
+use std::fmt::Write as _;
+use std::num::NonZeroU16;
+use std::{cmp, iter};
+
+use anyhow::{Context as _, Result};
+use indexmap::IndexMap;
+use palette::Srgb;
+use serde::Serialize;
+use strum::VariantArray as _;
+
+use crate::ascii::RawAsciiArt;
+use crate::color_util::ForegroundBackground;
+use crate::distros::Distro;
+use crate::neofetch_util::ascii_size;
+use crate::presets::{AnyPreset, CustomPreset};
+use crate::types::{AnsiMode, ListFormat};
+
+/// Minimum width of the color swatch of a preset.
+const SWATCH_WIDTH: u16 = 18;
+
+#[derive(Clone, Debug, Serialize)]
+struct PresetEntry {
+    name: String,
+    custom: bool,
+    colors: Vec<String>,
+    unique_colors: Vec<String>,
+    #[serde(skip)]
+    swatch: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct DistroEntry {
+    name: &'static str,
+    patterns: &'static [&'static str],
+    width: u16,
+    height: u16,
+    slots: Vec<u8>,
+    foreground: Vec<u8>,
+}
+
+/// Lists all presets, including the custom presets, with their colors.
+///
+/// The text format shows a swatch of each preset, colored in `color_mode`.
+pub fn list_presets(
+    custom_presets: &IndexMap<String, CustomPreset>,
+    color_mode: AnsiMode,
+    format: ListFormat,
+) -> Result<String> {
+    let entries = AnyPreset::all(custom_presets)
+        .map(|preset| {
+            let name = preset.as_ref().to_owned();
+            let color_profile = preset
+                .color_profile(custom_presets)
+                .with_context(|| format!("failed to get color profile of preset {name:?}"))?;
+            let width = cmp::max(
+                SWATCH_WIDTH,
+                color_profile
+                    .colors
+                    .len()
+                    .try_into()
+                    .with_context(|| format!("preset {name:?} has too many colors"))?,
+            );
+            let width = NonZeroU16::new(width).expect("swatch width should not be 0");
+            let swatch = color_profile
+                .color_text(
+                    "█".repeat(width.get().into()),
+                    color_mode,
+                    ForegroundBackground::Foreground,
+                    false,
+                )
+                .with_context(|| format!("failed to color swatch of preset {name:?}"))?;
+            Ok(PresetEntry {
+                custom: matches!(preset, AnyPreset::Custom(_)),
+                colors: color_profile.colors.iter().copied().map(hex).collect(),
+                unique_colors: color_profile
+                    .unique_colors()
+                    .colors
+                    .into_iter()
+                    .map(hex)
+                    .collect(),
+                swatch,
+                name,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    match format {
+        ListFormat::Json => {
+            let mut buf =
+                serde_json::to_string_pretty(&entries).context("failed to serialize presets")?;
+            buf.push('\n');
+            Ok(buf)
+        },
+        ListFormat::Text => {
+            let name_w = entries.iter().map(|e| e.name.len()).max().unwrap_or(0);
+            let mut buf = String::new();
+            for entry in entries {
+                write!(
+                    buf,
+                    "{name:name_w$}  {swatch}  {colors}",
+                    name = entry.name,
+                    swatch = entry.swatch,
+                    colors = entry.colors.join(" ")
+                )
+                .unwrap();
+                if entry.unique_colors != entry.colors {
+                    write!(
+                        buf,
+                        "  (unique: {unique_colors})",
+                        unique_colors = entry.unique_colors.join(" ")
+                    )
+                    .unwrap();
+                }
+                buf.push('\n');
+            }
+            Ok(buf)
+        },
+    }
+}
+
+/// Lists all distros with built-in ascii art, in the order they are matched
+/// when detecting the distro.
+pub fn list_distros(format: ListFormat) -> Result<String> {
+    let entries = Distro::VARIANTS
+        .iter()
+        .map(|distro| {
+            let asc = RawAsciiArt::from_distro(distro);
+            let (width, height) = ascii_size(&asc.asc).with_context(|| {
+                format!(
+                    "failed to get ascii size of distro {name}",
+                    name = distro.name()
+                )
+            })?;
+            Ok(DistroEntry {
+                name: distro.name(),
+                patterns: distro.patterns(),
+                width,
+                height,
+                slots: asc.color_slots().into_iter().map(u8::from).collect(),
+                foreground: asc.fg.into_iter().map(u8::from).collect(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    match format {
+        ListFormat::Json => {
+            let mut buf =
+                serde_json::to_string_pretty(&entries).context("failed to serialize distros")?;
+            buf.push('\n');
+            Ok(buf)
+        },
+        ListFormat::Text => {
+            let join = |indices: &[u8]| {
+                if indices.is_empty() {
+                    "-".to_owned()
+                } else {
+                    indices
+                        .iter()
+                        .map(u8::to_string)
+                        .collect::<Vec<_>>()
+                        .join(",")
+                }
+            };
+            let header = ["NAME", "SIZE", "SLOTS", "FG", "PATTERNS"].map(str::to_owned);
+            let rows: Vec<[String; 5]> = iter::once(header)
+                .chain(entries.iter().map(|entry| {
+                    [
+                        entry.name.to_owned(),
+                        format!("{w}x{h}", w = entry.width, h = entry.height),
+                        join(&entry.slots),
+                        join(&entry.foreground),
+                        entry.patterns.join(" | "),
+                    ]
+                }))
+                .collect();
+            let widths: Vec<usize> = (0..4)
+                .map(|i| {
+                    rows.iter()
+                        .map(|row| row[i].chars().count())
+                        .max()
+                        .unwrap_or(0)
+                })
+                .collect();
+            let mut buf = String::new();
+            for [name, size, slots, fg, patterns] in rows {
+                writeln!(
+                    buf,
+                    "{name:name_w$}  {size:size_w$}  {slots:slots_w$}  {fg:fg_w$}  {patterns}",
+                    name_w = widths[0],
+                    size_w = widths[1],
+                    slots_w = widths[2],
+                    fg_w = widths[3],
+                )
+                .unwrap();
+            }
+            Ok(buf)
+        },
+    }
+}
+
+fn hex(rgb: Srgb<u8>) -> String {
+    format!("#{rgb:x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    #[test]
+    fn list_distros_shows_detected_patterns() {
+        let json: Value = serde_json::from_str(&list_distros(ListFormat::Json).unwrap()).unwrap();
+        let entries = json.as_array().unwrap();
+        assert_eq!(entries.len(), Distro::VARIANTS.len());
+        let adelie = entries
+            .iter()
+            .find(|entry| entry["name"] == "Adélie")
+            .unwrap();
+        assert_eq!(
+            adelie["patterns"],
+            serde_json::json!(["adélie*", "adelie*"])
+        );
+
+        for pattern in entries
+            .iter()
+            .flat_map(|entry| entry["patterns"].as_array().unwrap())
+        {
+            let pattern = pattern.as_str().unwrap();
+            assert!(!pattern.contains(['"', '\'', '|']), "{pattern}");
+            assert_eq!(pattern, pattern.to_lowercase());
+        }
+
+        let text = list_distros(ListFormat::Text).unwrap();
+        assert!(text.starts_with("NAME "));
+        assert!(text
+            .lines()
+            .any(|line| line.starts_with("Adélie ") && line.ends_with("  adélie* | adelie*")));
+    }
+}
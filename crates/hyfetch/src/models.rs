@@ -1,23 +1,107 @@
+use std::path::{Path, PathBuf};
+use std::{fs, io, mem};
+
+use anyhow::{bail, Context as _, Result};
+use indexmap::IndexMap;
+use serde::de::IntoDeserializer as _;
 use serde::{Deserialize, Serialize};
+use serde_json::ser::PrettyFormatter;
+use serde_json::{json, Map, Value};
+use toml_edit::{DocumentMut, Item, Table};
+use tracing::debug;
 
 use crate::color_util::Lightness;
+use crate::distros::Distro;
 use crate::neofetch_util::ColorAlignment;
-use crate::presets::Preset;
-use crate::types::{AnsiMode, Backend, TerminalTheme};
+use crate::presets::{AnyPreset, CustomPreset};
+use crate::types::{AnsiMode, Backend, Blend, LogoStyle, TerminalTheme};
+
+/// Config keys chosen in the wizard, which are what gets saved to a profile.
+const PROFILE_KEYS: [&str; 7] = [
+    "preset",
+    "mode",
+    "light_dark",
+    "lightness",
+    "color_align",
+    "distro_color_align",
+    "backend",
+];
+
+/// Words for going to the next or previous page when choosing a preset in the
+/// config wizard, which custom presets can't be named.
+pub const PAGE_NAVIGATION_WORDS: [&str; 4] = ["next", "n", "prev", "p"];
+
+/// Version of the config schema written by this version of hyfetch.
+///
+/// Configs without a `version` field (including the ones written by the
+/// Python implementation) are version 0.
+pub const CONFIG_VERSION: u32 = 1;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Config {
-    pub preset: Preset,
+    #[serde(default)]
+    pub version: u32,
+    pub preset: AnyPreset,
     pub mode: AnsiMode,
     pub light_dark: TerminalTheme,
     pub lightness: Option<Lightness>,
     pub color_align: ColorAlignment,
+    /// Color alignments used instead of `color_align` for the ascii art of
+    /// specific distros, keyed by [`Distro::name`]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub distro_color_align: IndexMap<String, ColorAlignment>,
+    #[serde(default)]
+    pub blend: Blend,
+    #[serde(default)]
+    pub style: LogoStyle,
     pub backend: Backend,
     #[serde(default)]
     #[serde(with = "self::args_serde")]
     pub args: Option<Vec<String>>,
     pub distro: Option<String>,
     pub pride_month_disable: bool,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub custom_presets: IndexMap<String, CustomPreset>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub profiles: IndexMap<String, Profile>,
+    /// Fields not known to this version of hyfetch, kept as is so that they
+    /// survive a round trip.
+    #[serde(flatten)]
+    pub extra: IndexMap<String, Value>,
+}
+
+/// Named set of config values, applied on top of the config files when
+/// selected.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Profile {
+    /// Selects the profile automatically when matched.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub when: Option<ProfileRule>,
+    #[serde(flatten)]
+    pub values: Map<String, Value>,
+}
+
+/// Conditions for selecting a profile automatically.
+///
+/// All of the given conditions must match.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ProfileRule {
+    /// Glob pattern for the hostname, e.g. `"*.example.com"`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// Glob pattern for `$TERM`, e.g. `"xterm-*"`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub term: Option<String>,
+    /// Whether hyfetch is running in an SSH session
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh: Option<bool>,
 }
 
 impl Config {
@@ -36,6 +120,440 @@ impl Config {
         self.lightness
             .unwrap_or_else(|| Self::default_lightness(self.light_dark))
     }
+
+    /// Gets the color alignment for the ascii art of a distro.
+    ///
+    /// Distro names are matched case-insensitively.
+    pub fn color_align_for(&self, distro: Option<&Distro>) -> &ColorAlignment {
+        distro
+            .and_then(|distro| {
+                self.distro_color_align
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(distro.name()))
+            })
+            .map_or(&self.color_align, |(_, color_align)| color_align)
+    }
+
+    /// Sets the color alignment chosen for the ascii art of a distro.
+    ///
+    /// Custom alignments depend on the color slots of the ascii art, so they
+    /// are only set for the distro, if it is known.
+    pub fn set_color_align(&mut self, color_align: ColorAlignment, distro: Option<&Distro>) {
+        match (color_align, distro) {
+            (color_align @ ColorAlignment::Custom { .. }, Some(distro)) => {
+                self.distro_color_align
+                    .insert(distro.name().to_owned(), color_align);
+            },
+            (color_align, _) => {
+                self.color_align = color_align;
+            },
+        }
+    }
+}
+
+/// Upgrades a config to [`CONFIG_VERSION`], in place.
+///
+/// Returns `true` if the config was changed.
+pub fn migrate_config(config: &mut Value) -> Result<bool> {
+    let obj = config
+        .as_object_mut()
+        .context("config should be a JSON object")?;
+
+    let version = match obj.get("version") {
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .with_context(|| format!("invalid config version {version}"))?,
+        None => 0,
+    };
+    if version > CONFIG_VERSION {
+        debug!(version, "config is newer than this version of hyfetch");
+        return Ok(false);
+    }
+    if version == CONFIG_VERSION {
+        return Ok(false);
+    }
+
+    if version < 1 {
+        debug!("migrating config from version 0");
+
+        // Replaced by `pride_month_disable` and the cache file; see
+        // https://github.com/hykilpikonna/hyfetch/issues/136
+        obj.remove("pride_month_shown");
+
+        // Fields that were added later in the Python implementation
+        let defaults = [
+            ("light_dark", json!("dark")),
+            ("lightness", Value::Null),
+            ("color_align", json!({ "mode": "horizontal" })),
+            ("backend", json!("neofetch")),
+            ("args", Value::Null),
+            ("distro", Value::Null),
+            ("pride_month_disable", json!(false)),
+        ];
+        for (key, default) in defaults {
+            obj.entry(key).or_insert(default);
+        }
+
+        // The Python implementation writes an empty string for no arguments
+        if obj.get("args").and_then(Value::as_str) == Some("") {
+            obj.insert("args".to_owned(), Value::Null);
+        }
+    }
+
+    obj.insert("version".to_owned(), json!(CONFIG_VERSION));
+
+    Ok(true)
+}
+
+/// File format of a config file.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Picks the format by file extension, defaulting to JSON.
+    pub fn from_path<P>(path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        match path.as_ref().extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => Self::Toml,
+            _ => Self::Json,
+        }
+    }
+}
+
+/// Parses the content of a config file into a JSON value.
+pub fn parse_config(buf: &str, format: ConfigFormat) -> Result<Value> {
+    match format {
+        ConfigFormat::Json => serde_json::from_str(buf).context("failed to parse JSON"),
+        ConfigFormat::Toml => toml_edit::de::from_str(buf).context("failed to parse TOML"),
+    }
+}
+
+/// Serializes a config into the content of a config file.
+///
+/// For TOML, `existing` is the current content of the file, if any. It is
+/// edited in place so that comments and formatting are kept.
+pub fn serialize_config<T>(
+    config: &T,
+    format: ConfigFormat,
+    existing: Option<&str>,
+) -> Result<String>
+where
+    T: Serialize + ?Sized,
+{
+    match format {
+        ConfigFormat::Json => {
+            let mut buf = Vec::new();
+            let mut serializer = serde_json::Serializer::with_formatter(
+                &mut buf,
+                PrettyFormatter::with_indent(b"    "),
+            );
+            config
+                .serialize(&mut serializer)
+                .context("failed to serialize config to JSON")?;
+            Ok(String::from_utf8(buf).expect("JSON should be valid UTF-8"))
+        },
+        ConfigFormat::Toml => {
+            // Go through JSON, which turns map keys into strings
+            let mut value = to_json_value(config)?;
+            strip_nulls(&mut value);
+            let mut new_doc = toml_edit::ser::to_document(&value)
+                .context("failed to serialize config to TOML")?;
+            expand_inline_tables(new_doc.as_table_mut());
+
+            let Some(existing) = existing else {
+                return Ok(new_doc.to_string());
+            };
+            let mut doc: DocumentMut = existing.parse().context("failed to parse existing TOML")?;
+            merge_toml_table(doc.as_table_mut(), new_doc.as_table());
+            Ok(doc.to_string())
+        },
+    }
+}
+
+/// Reads a config file as is, without migrating it.
+///
+/// Returns `None` if the config file does not exist.
+pub fn read_config_value(path: &Path) -> Result<Option<Value>> {
+    let Some(buf) = read_config_buf(path)? else {
+        return Ok(None);
+    };
+
+    let value = parse_config(&buf, ConfigFormat::from_path(path))
+        .with_context(|| format!("failed to parse config from file {path:?}"))?;
+
+    Ok(Some(value))
+}
+
+/// Reads a config file and upgrades it to [`CONFIG_VERSION`].
+///
+/// If `rewrite` is set and the config was migrated, the file is updated in
+/// place, keeping the original next to it as `<file>.bak`.
+///
+/// Returns `None` if the config file does not exist.
+#[tracing::instrument(level = "debug")]
+pub fn load_config_value(path: &Path, rewrite: bool) -> Result<Option<Value>> {
+    let Some(buf) = read_config_buf(path)? else {
+        return Ok(None);
+    };
+
+    let mut value = parse_config(&buf, ConfigFormat::from_path(path))
+        .with_context(|| format!("failed to parse config from file {path:?}"))?;
+    let migrated = migrate_config(&mut value)
+        .with_context(|| format!("failed to migrate config from file {path:?}"))?;
+
+    if migrated && rewrite {
+        // Keep the original file around in case the migration went wrong
+        let backup_path = {
+            let mut backup_path = path.as_os_str().to_owned();
+            backup_path.push(".bak");
+            PathBuf::from(backup_path)
+        };
+        fs::write(&backup_path, &buf)
+            .with_context(|| format!("failed to write config backup to file {backup_path:?}"))?;
+        save_config(path, &value).context("failed to save migrated config")?;
+        debug!(?backup_path, "migrated config");
+    }
+
+    Ok(Some(value))
+}
+
+fn read_config_buf(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(buf) => Ok(Some(buf)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).with_context(|| format!("failed to read from file {path:?}")),
+    }
+}
+
+/// Saves config to file.
+pub fn save_config<T>(path: &Path, config: &T) -> Result<()>
+where
+    T: Serialize + ?Sized,
+{
+    match path.parent().context("invalid config file path")? {
+        parent_path if parent_path != Path::new("") => {
+            fs::create_dir_all(parent_path)
+                .with_context(|| format!("failed to create dir {parent_path:?}"))?;
+        },
+        _ => {
+            // Nothing to do if it's a relative path with one component
+        },
+    }
+    let format = ConfigFormat::from_path(path);
+    let existing = match fs::read_to_string(path) {
+        Ok(buf) => Some(buf),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read from file {path:?}"));
+        },
+    };
+    let buf = serialize_config(config, format, existing.as_deref())
+        .with_context(|| format!("failed to serialize config for file {path:?}"))?;
+    fs::write(path, buf).with_context(|| format!("failed to write config to file {path:?}"))?;
+    debug!(?path, "saved config");
+
+    Ok(())
+}
+
+/// Saves a config created by the wizard.
+///
+/// The created values are merged into the existing config file, so that its
+/// profiles, unknown fields and color alignments for other distros are kept.
+/// If `profile` is given, the wizard's choices are merged into that profile
+/// instead, and the rest of the config file is left as is.
+///
+/// `distro` is the distro whose ascii art the config was created with. Its
+/// existing color alignment is replaced by the one chosen in the wizard.
+pub fn save_created_config(
+    path: &Path,
+    config: &Config,
+    distro: Option<&Distro>,
+    profile: Option<&str>,
+) -> Result<()> {
+    let existing = load_config_value(path, false).context("failed to load existing config")?;
+    let Value::Object(mut values) = to_json_value(config)? else {
+        unreachable!("config should serialize to a JSON object");
+    };
+
+    // Without an existing file, the created config also serves as the base
+    let mut base = existing.unwrap_or_else(|| Value::Object(values.clone()));
+    let base_obj = base
+        .as_object_mut()
+        .context("config should be a JSON object")?;
+    let dst = match profile {
+        None => base_obj,
+        Some(name) => {
+            values.retain(|key, _| PROFILE_KEYS.contains(&key.as_str()));
+            base_obj
+                .entry("profiles")
+                .or_insert_with(|| json!({}))
+                .as_object_mut()
+                .context("`profiles` should be a JSON object")?
+                .entry(name)
+                .or_insert_with(|| json!({}))
+                .as_object_mut()
+                .with_context(|| format!("profile {name:?} should be a JSON object"))?
+        },
+    };
+    merge_created_values(dst, values, distro);
+    save_config(path, &base)
+}
+
+/// Merges the values created by the wizard into a config or profile.
+fn merge_created_values(
+    dst: &mut Map<String, Value>,
+    src: Map<String, Value>,
+    distro: Option<&Distro>,
+) {
+    if let (Some(distro), Some(Value::Object(distro_color_align))) =
+        (distro, dst.get_mut("distro_color_align"))
+    {
+        distro_color_align.retain(|name, _| !name.eq_ignore_ascii_case(distro.name()));
+        if distro_color_align.is_empty() {
+            dst.remove("distro_color_align");
+        }
+    }
+
+    for (key, value) in src {
+        match (dst.get_mut(&key), value) {
+            (Some(Value::Object(dst_value)), Value::Object(value))
+                if key == "distro_color_align" =>
+            {
+                dst_value.extend(value);
+            },
+            (_, value) => {
+                dst.insert(key, value);
+            },
+        }
+    }
+}
+
+/// Serializes to a JSON value, going through a JSON string so that `f32`
+/// values are not widened to `f64`.
+pub(crate) fn to_json_value<T>(value: &T) -> Result<Value>
+where
+    T: Serialize + ?Sized,
+{
+    serde_json::to_string(value)
+        .and_then(|s| serde_json::from_str(&s))
+        .context("failed to serialize config")
+}
+
+/// Removes `null`s, which can't be represented in TOML.
+fn strip_nulls(value: &mut Value) {
+    match value {
+        Value::Object(obj) => {
+            obj.retain(|_, v| !v.is_null());
+            obj.values_mut().for_each(strip_nulls);
+        },
+        Value::Array(arr) => arr.iter_mut().for_each(strip_nulls),
+        _ => {},
+    }
+}
+
+/// Turns inline tables into standard tables, e.g. `[color_align]`.
+fn expand_inline_tables(table: &mut Table) {
+    let mut has_values = false;
+    for (_, item) in table.iter_mut() {
+        if item.is_inline_table() {
+            let inline = mem::take(item);
+            *item = Item::Table(inline.into_table().expect("item should be an inline table"));
+        }
+        match item {
+            Item::Table(table) => expand_inline_tables(table),
+            _ => has_values = true,
+        }
+    }
+    // Avoid empty headers for tables that only contain other tables
+    table.set_implicit(!has_values);
+}
+
+/// Updates `dst` to have the content of `src`, keeping the comments and
+/// formatting of `dst` where the values didn't change.
+///
+/// Keys missing from `src` are removed from `dst`, as that is how `null`
+/// values are written in TOML.
+fn merge_toml_table(dst: &mut Table, src: &Table) {
+    dst.retain(|key, _| src.contains_key(key));
+
+    for (key, src_item) in src.iter() {
+        let Some(dst_item) = dst.get_mut(key) else {
+            dst.insert(key, src_item.clone());
+            continue;
+        };
+        match (dst_item, src_item) {
+            (Item::Table(dst_table), Item::Table(src_table)) => {
+                merge_toml_table(dst_table, src_table);
+            },
+            (Item::Value(dst_value), Item::Value(src_value)) => {
+                merge_toml_value(dst_value, src_value.clone());
+            },
+            (Item::Value(dst_value), Item::Table(src_table)) if dst_value.is_inline_table() => {
+                let src_value =
+                    toml_edit::Value::InlineTable(src_table.clone().into_inline_table());
+                merge_toml_value(dst_value, src_value);
+            },
+            (dst_item, src_item) => *dst_item = src_item.clone(),
+        }
+    }
+}
+
+fn merge_toml_value(dst: &mut toml_edit::Value, mut src: toml_edit::Value) {
+    let to_json = |v: &toml_edit::Value| Value::deserialize(v.clone().into_deserializer()).ok();
+    if to_json(dst).is_some() && to_json(dst) == to_json(&src) {
+        return;
+    }
+    *src.decor_mut() = dst.decor().clone();
+    *dst = src;
+}
+
+/// Reads only the custom presets from a config file, without requiring the
+/// rest of the config to be valid.
+///
+/// Returns an empty map if the config file does not exist, or an error if a
+/// custom preset is named after one of the [`PAGE_NAVIGATION_WORDS`].
+pub fn read_custom_presets<P>(path: P) -> Result<IndexMap<String, CustomPreset>>
+where
+    P: AsRef<Path>,
+{
+    #[derive(Deserialize)]
+    struct CustomPresetsOnly {
+        #[serde(default)]
+        custom_presets: IndexMap<String, CustomPreset>,
+    }
+
+    let path = path.as_ref();
+
+    let buf = match fs::read_to_string(path) {
+        Ok(buf) => buf,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Ok(IndexMap::new());
+        },
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read from file {path:?}"));
+        },
+    };
+
+    let CustomPresetsOnly { custom_presets } = parse_config(&buf, ConfigFormat::from_path(path))
+        .and_then(|value| serde_json::from_value(value).map_err(Into::into))
+        .with_context(|| format!("failed to parse custom presets from file {path:?}"))?;
+    if let Some(name) = custom_presets
+        .keys()
+        .find(|name| PAGE_NAVIGATION_WORDS.contains(&name.as_str()))
+    {
+        bail!(
+            "custom preset {name:?} should not be named one of {{{words}}}",
+            words = PAGE_NAVIGATION_WORDS.join(",")
+        );
+    }
+
+    Ok(custom_presets)
 }
 
 mod args_serde {
@@ -121,3 +639,232 @@ mod args_serde {
         deserializer.deserialize_option(OptionVisitor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::presets::Preset;
+
+    #[test]
+    fn migrate_python_config() {
+        let mut value = json!({
+            "preset": "rainbow",
+            "mode": "8bit",
+            "args": "",
+            "pride_month_shown": [2022],
+            "unknown": 42,
+        });
+        assert!(migrate_config(&mut value).unwrap());
+        assert!(!migrate_config(&mut value).unwrap());
+
+        let config: Config = serde_json::from_value(value).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.args, None);
+        assert_eq!(config.extra.get("unknown"), Some(&json!(42)));
+        assert!(!config.extra.contains_key("pride_month_shown"));
+    }
+
+    #[test]
+    fn save_created_config_keeps_existing_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hyfetch.json");
+        let existing = json!({
+            "version": CONFIG_VERSION,
+            "preset": "rainbow",
+            "mode": "8bit",
+            "light_dark": "dark",
+            "lightness": null,
+            "color_align": { "mode": "horizontal" },
+            "backend": "neofetch",
+            "distro": null,
+            "pride_month_disable": false,
+            "profiles": { "work": { "preset": "lesbian" } },
+            "unknown": 42,
+        });
+        fs::write(&path, existing.to_string()).unwrap();
+
+        let mut config: Config = serde_json::from_value(existing).unwrap();
+        config.preset = Preset::Transgender.into();
+        config.profiles.clear();
+        config.extra.clear();
+        save_created_config(&path, &config, None, None).unwrap();
+
+        let saved = load_config_value(&path, false).unwrap().unwrap();
+        assert_eq!(saved["preset"], json!("transgender"));
+        assert_eq!(saved["unknown"], json!(42));
+        assert_eq!(
+            saved["profiles"],
+            json!({ "work": { "preset": "lesbian" } })
+        );
+    }
+
+    #[test]
+    fn custom_presets_named_like_navigation_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hyfetch.json");
+        fs::write(
+            &path,
+            json!({ "custom_presets": { "mine": { "colors": ["#FF0000"] } } }).to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            read_custom_presets(&path)
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            ["mine"]
+        );
+
+        fs::write(
+            &path,
+            json!({ "custom_presets": { "next": { "colors": ["#FF0000"] } } }).to_string(),
+        )
+        .unwrap();
+        let err = read_custom_presets(&path).unwrap_err();
+        assert!(format!("{err:#}").contains("\"next\" should not be named"));
+    }
+
+    #[test]
+    fn distro_color_align_overrides() {
+        let mut config: Config = serde_json::from_value(json!({
+            "preset": "rainbow",
+            "mode": "rgb",
+            "light_dark": "dark",
+            "lightness": null,
+            "color_align": { "mode": "vertical" },
+            "distro_color_align": { "arch": { "mode": "horizontal" } },
+            "backend": "native",
+            "distro": null,
+            "pride_month_disable": false,
+        }))
+        .unwrap();
+        let arch = Distro::detect("arch").unwrap();
+        let fedora = Distro::detect("fedora").unwrap();
+        assert_eq!(
+            config.color_align_for(Some(&arch)),
+            &ColorAlignment::Horizontal
+        );
+        assert_eq!(
+            config.color_align_for(Some(&fedora)),
+            &ColorAlignment::Vertical
+        );
+        assert_eq!(config.color_align_for(None), &ColorAlignment::Vertical);
+
+        let custom: ColorAlignment = "custom:1=0".parse().unwrap();
+        config.set_color_align(custom.clone(), Some(&fedora));
+        assert_eq!(config.color_align_for(Some(&fedora)), &custom);
+        assert_eq!(config.color_align, ColorAlignment::Vertical);
+    }
+
+    #[test]
+    fn save_created_config_keeps_other_distros() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hyfetch.json");
+        let arch = Distro::detect("arch").unwrap();
+        let fedora = Distro::detect("fedora").unwrap();
+        let mut config: Config = serde_json::from_value(json!({
+            "preset": "rainbow",
+            "mode": "rgb",
+            "light_dark": "dark",
+            "lightness": null,
+            "color_align": { "mode": "horizontal" },
+            "backend": "native",
+            "distro": null,
+            "pride_month_disable": false,
+        }))
+        .unwrap();
+        let arch_align: ColorAlignment = "custom:1=0,2=1".parse().unwrap();
+        config.set_color_align(arch_align.clone(), Some(&arch));
+        save_created_config(&path, &config, Some(&arch), None).unwrap();
+
+        // Run the wizard again on Fedora, which has a different ascii art
+        config.distro_color_align.clear();
+        let fedora_align: ColorAlignment = "custom:1=1,2=0".parse().unwrap();
+        config.set_color_align(fedora_align.clone(), Some(&fedora));
+        save_created_config(&path, &config, Some(&fedora), None).unwrap();
+
+        let saved = load_config_value(&path, false).unwrap().unwrap();
+        let saved: Config = serde_json::from_value(saved).unwrap();
+        assert_eq!(saved.color_align_for(Some(&arch)), &arch_align);
+        assert_eq!(saved.color_align_for(Some(&fedora)), &fedora_align);
+
+        // Choosing a global alignment on Fedora drops its override
+        config.distro_color_align.clear();
+        config.set_color_align(ColorAlignment::Vertical, Some(&fedora));
+        save_created_config(&path, &config, Some(&fedora), None).unwrap();
+
+        let saved = load_config_value(&path, false).unwrap().unwrap();
+        let saved: Config = serde_json::from_value(saved).unwrap();
+        assert_eq!(saved.color_align_for(Some(&arch)), &arch_align);
+        assert_eq!(
+            saved.color_align_for(Some(&fedora)),
+            &ColorAlignment::Vertical
+        );
+    }
+
+    const TOML_CONFIG: &str = r#"# Written by hand
+version = 1
+preset = "rainbow" # my favorite
+mode = "rgb"
+light_dark = "dark"
+lightness = 0.5
+backend = "native"
+pride_month_disable = false
+
+# How the colors are laid out
+[color_align]
+mode = "horizontal"
+"#;
+
+    #[test]
+    fn toml_config_round_trip() {
+        let value = parse_config(TOML_CONFIG, ConfigFormat::Toml).unwrap();
+        let config: Config = serde_json::from_value(value).unwrap();
+        assert_eq!(config.color_align, ColorAlignment::Horizontal);
+
+        let buf = serialize_config(&config, ConfigFormat::Toml, None).unwrap();
+        let round_trip: Config =
+            serde_json::from_value(parse_config(&buf, ConfigFormat::Toml).unwrap()).unwrap();
+        assert_eq!(
+            to_json_value(&round_trip).unwrap(),
+            to_json_value(&config).unwrap()
+        );
+    }
+
+    #[test]
+    fn toml_rewrite_keeps_comments() {
+        let value = parse_config(TOML_CONFIG, ConfigFormat::Toml).unwrap();
+        let mut config: Config = serde_json::from_value(value).unwrap();
+        config.preset = Preset::Transgender.into();
+        config.lightness = None;
+        config.color_align = ColorAlignment::Vertical;
+
+        let buf = serialize_config(&config, ConfigFormat::Toml, Some(TOML_CONFIG)).unwrap();
+        assert!(buf.starts_with("# Written by hand\nversion = 1\n"));
+        assert!(buf.contains("preset = \"transgender\" # my favorite\n"));
+        assert!(buf.contains("\nmode = \"rgb\"\n"));
+        assert!(buf.contains("# How the colors are laid out\n[color_align]\n"));
+        assert!(buf.contains("\nmode = \"vertical\"\n"));
+        // `null` values are left out
+        assert!(!buf.contains("lightness"));
+    }
+
+    #[test]
+    fn toml_tables_without_nulls() {
+        let mut value = json!({
+            "a": null,
+            "b": [{ "c": null, "d": 1 }],
+            "e": { "f": null, "g": { "h": 2 } },
+        });
+        strip_nulls(&mut value);
+        assert_eq!(
+            value,
+            json!({ "b": [{ "d": 1 }], "e": { "g": { "h": 2 } } })
+        );
+
+        let mut doc = toml_edit::ser::to_document(&value).unwrap();
+        expand_inline_tables(doc.as_table_mut());
+        // `e` only contains a table, so it doesn't get a header of its own
+        assert_eq!(doc.to_string(), "b = [{ d = 1 }]\n\n[e.g]\nh = 2\n");
+    }
+}
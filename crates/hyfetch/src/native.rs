@@ -0,0 +1,487 @@
+use std::fmt::Write as _;
+use std::io::{self, Write as _};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use std::{env, fs, iter};
+
+use anyhow::{Context as _, Result};
+use indexmap::IndexMap;
+use tracing::debug;
+
+use crate::ascii::RecoloredAsciiArt;
+use crate::distros::Distro;
+
+/// Paths where the os-release file may be found, in order of priority.
+///
+/// See <https://www.freedesktop.org/software/systemd/man/latest/os-release.html>
+const OS_RELEASE_PATHS: [&str; 2] = ["/etc/os-release", "/usr/lib/os-release"];
+
+/// Path of the lsb-release file, for distros that predate os-release.
+const LSB_RELEASE_PATH: &str = "/etc/lsb-release";
+
+/// Keys of the os-release file that may name the distro, in order of priority.
+const OS_RELEASE_DISTRO_KEYS: [&str; 4] = ["ID", "ID_LIKE", "NAME", "PRETTY_NAME"];
+
+/// Keys of the lsb-release file that may name the distro, in order of priority.
+const LSB_RELEASE_DISTRO_KEYS: [&str; 2] = ["DISTRIB_ID", "DISTRIB_DESCRIPTION"];
+
+/// Number of spaces between the ascii art and the system information.
+const GAP: usize = 3;
+
+/// System information gathered without running any external program.
+#[derive(Clone, Debug, Default)]
+pub struct SystemInfo {
+    pub user: Option<String>,
+    pub hostname: Option<String>,
+    pub os: Option<String>,
+    pub kernel: Option<String>,
+    pub uptime: Option<Duration>,
+    pub packages: Vec<(&'static str, usize)>,
+    pub shell: Option<String>,
+    pub terminal: Option<String>,
+    pub cpu: Option<String>,
+    /// Used and total memory, in KiB
+    pub memory: Option<(u64, u64)>,
+}
+
+impl SystemInfo {
+    /// Collects system information from `/proc`, `/etc` and the environment.
+    ///
+    /// Fields that can't be determined on the current system are left empty.
+    #[tracing::instrument(level = "debug")]
+    pub fn collect() -> Self {
+        let os_release = os_release()
+            .map_err(|err| debug!(%err, "could not read os-release"))
+            .ok()
+            .flatten();
+        let os = os_release
+            .as_ref()
+            .and_then(|os_release| {
+                os_release
+                    .get("PRETTY_NAME")
+                    .or_else(|| os_release.get("NAME"))
+                    .cloned()
+            })
+            .unwrap_or_else(|| env::consts::OS.to_owned());
+
+        Self {
+            user: env::var("USER")
+                .or_else(|_| env::var("USERNAME"))
+                .ok()
+                .filter(|user| !user.is_empty()),
+            hostname: hostname(),
+            os: Some(format!("{os} {arch}", arch = env::consts::ARCH)),
+            kernel: read_trimmed("/proc/sys/kernel/osrelease"),
+            uptime: read_trimmed("/proc/uptime").and_then(|uptime| {
+                let secs: f64 = uptime.split_whitespace().next()?.parse().ok()?;
+                Duration::try_from_secs_f64(secs).ok()
+            }),
+            packages: packages(),
+            shell: env::var_os("SHELL").and_then(|shell| {
+                Path::new(&shell)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            }),
+            terminal: env::var("TERM_PROGRAM")
+                .or_else(|_| env::var("TERM"))
+                .ok()
+                .filter(|term| !term.is_empty()),
+            cpu: cpu(),
+            memory: memory(),
+        }
+    }
+
+    /// Formats the system information as lines of text, neofetch style.
+    pub fn to_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        if let (Some(user), Some(hostname)) = (&self.user, &self.hostname) {
+            let title = format!("{user}@{hostname}");
+            lines.push(format!("\x1b[1m{title}\x1b[22m"));
+            lines.push("-".repeat(title.chars().count()));
+        }
+
+        let mut push = |key: &str, value: Option<String>| {
+            if let Some(value) = value {
+                lines.push(format!("\x1b[1m{key}\x1b[22m: {value}"));
+            }
+        };
+
+        push("OS", self.os.clone());
+        push("Kernel", self.kernel.clone());
+        push("Uptime", self.uptime.map(format_uptime));
+        push(
+            "Packages",
+            if self.packages.is_empty() {
+                None
+            } else {
+                Some(
+                    self.packages
+                        .iter()
+                        .map(|(manager, count)| format!("{count} ({manager})"))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                )
+            },
+        );
+        push("Shell", self.shell.clone());
+        push("Terminal", self.terminal.clone());
+        push("CPU", self.cpu.clone());
+        push(
+            "Memory",
+            self.memory.map(|(used, total)| {
+                format!(
+                    "{used}MiB / {total}MiB",
+                    used = used.div_euclid(1024),
+                    total = total.div_euclid(1024)
+                )
+            }),
+        );
+
+        lines
+    }
+}
+
+/// Reads and parses the os-release file.
+///
+/// Returns `None` if no os-release file exists.
+pub fn os_release() -> Result<Option<IndexMap<String, String>>> {
+    for path in OS_RELEASE_PATHS {
+        match fs::read_to_string(path) {
+            Ok(buf) => return Ok(Some(parse_os_release(&buf))),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read from file {path:?}"));
+            },
+        }
+    }
+
+    Ok(None)
+}
+
+/// Gets the last modification time of the os-release file.
+///
+/// Returns `None` if no os-release file exists.
+pub fn os_release_modified() -> Option<SystemTime> {
+    OS_RELEASE_PATHS
+        .iter()
+        .find_map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+}
+
+/// Parses the `KEY=value` lines of an os-release (or lsb-release) file.
+pub fn parse_os_release(buf: &str) -> IndexMap<String, String> {
+    buf.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.starts_with('#') {
+                return None;
+            }
+            let (key, value) = line.split_once('=')?;
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            Some((key.to_owned(), value.replace(r#"\""#, r#"""#)))
+        })
+        .collect()
+}
+
+/// Gets the hostname without running any external program.
+pub fn hostname() -> Option<String> {
+    read_trimmed("/proc/sys/kernel/hostname")
+        .or_else(|| read_trimmed("/etc/hostname"))
+        .or_else(|| env::var("HOSTNAME").ok())
+        .or_else(|| env::var("COMPUTERNAME").ok())
+}
+
+/// Gets the distro name without running any external program.
+pub(crate) fn get_distro_name() -> Result<String> {
+    let os_release = os_release().context("failed to read os-release")?;
+    let name = os_release
+        .as_ref()
+        .and_then(|os_release| os_release.get("NAME"))
+        .cloned()
+        .unwrap_or_else(|| env::consts::OS.to_owned());
+    Ok(name)
+}
+
+/// Detects the distro from the os-release and lsb-release files, without
+/// running any external program.
+///
+/// Returns the value that matched, or `None` if nothing matched.
+pub fn detect_distro() -> Result<Option<(String, Distro)>> {
+    let os_release = os_release().context("failed to read os-release")?;
+    let lsb_release = match fs::read_to_string(LSB_RELEASE_PATH) {
+        Ok(buf) => Some(parse_os_release(&buf)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("failed to read from file {LSB_RELEASE_PATH:?}"));
+        },
+    };
+    Ok(match_distro(os_release.as_ref(), lsb_release.as_ref()))
+}
+
+/// Matches the distro-naming keys of the release files against the distro
+/// patterns, in order of priority.
+///
+/// Derivatives that are unknown by `ID` still match by `ID_LIKE`.
+fn match_distro(
+    os_release: Option<&IndexMap<String, String>>,
+    lsb_release: Option<&IndexMap<String, String>>,
+) -> Option<(String, Distro)> {
+    let releases = [
+        (os_release, &OS_RELEASE_DISTRO_KEYS[..]),
+        (lsb_release, &LSB_RELEASE_DISTRO_KEYS[..]),
+    ];
+    for (release, keys) in releases {
+        let Some(release) = release else {
+            continue;
+        };
+        for &key in keys {
+            let Some(value) = release.get(key) else {
+                continue;
+            };
+            // `ID_LIKE` lists the closest related distro first
+            let names: Vec<&str> = if key == "ID_LIKE" {
+                value.split_whitespace().collect()
+            } else {
+                vec![value.trim()]
+            };
+            for name in names.into_iter().filter(|name| !name.is_empty()) {
+                if let Some(distro) = Distro::detect(name) {
+                    debug!(
+                        key,
+                        name,
+                        distro = distro.name(),
+                        "matched distro from release file"
+                    );
+                    return Some((name.to_owned(), distro));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Prints the ascii art with the system information laid out beside it.
+///
+/// If `capture` is set, the output is returned instead of written to stdout.
+#[tracing::instrument(level = "debug", skip(asc))]
+pub(crate) fn run_native(
+    asc: RecoloredAsciiArt,
+    args: Option<&Vec<String>>,
+    capture: bool,
+) -> Result<Option<String>> {
+    if let Some(args) = args {
+        debug!(?args, "arguments are ignored by the native backend");
+    }
+
+    let info = SystemInfo::collect();
+    debug!(?info, "system info");
+    let info = info.to_lines();
+
+    let blank = " ".repeat(usize::from(asc.w));
+    let mut buf = String::new();
+    let mut art_lines = asc.lines.iter().map(String::as_str);
+    let mut info_lines = info.iter().map(String::as_str);
+    loop {
+        match (art_lines.next(), info_lines.next()) {
+            (None, None) => break,
+            (Some(art_line), None) => writeln!(buf, "{art_line}").unwrap(),
+            (art_line, Some(info_line)) => writeln!(
+                buf,
+                "{art_line}{gap}{info_line}",
+                art_line = art_line.unwrap_or(&blank),
+                gap = " ".repeat(GAP)
+            )
+            .unwrap(),
+        }
+    }
+
+    if capture {
+        return Ok(Some(buf));
+    }
+
+    io::stdout()
+        .write_all(buf.as_bytes())
+        .context("failed to write to stdout")?;
+
+    Ok(None)
+}
+
+fn read_trimmed<P>(path: P) -> Option<String>
+where
+    P: AsRef<Path>,
+{
+    fs::read_to_string(path)
+        .ok()
+        .map(|s| s.trim().to_owned())
+        .filter(|s| !s.is_empty())
+}
+
+fn format_uptime(uptime: Duration) -> String {
+    let mins = uptime.as_secs().div_euclid(60);
+    let (days, hours, mins) = (
+        mins.div_euclid(60 * 24),
+        mins.div_euclid(60).rem_euclid(24),
+        mins.rem_euclid(60),
+    );
+    let plural = |n: u64, unit: &str| {
+        if n == 1 {
+            format!("{n} {unit}")
+        } else {
+            format!("{n} {unit}s")
+        }
+    };
+    let parts: Vec<String> = [(days, "day"), (hours, "hour"), (mins, "min")]
+        .into_iter()
+        .filter(|&(n, _)| n > 0)
+        .map(|(n, unit)| plural(n, unit))
+        .collect();
+    if parts.is_empty() {
+        plural(0, "min")
+    } else {
+        parts.join(", ")
+    }
+}
+
+fn cpu() -> Option<String> {
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo").ok()?;
+    let value = |line: &str| line.split_once(':').map(|(_, v)| v.trim().to_owned());
+    let model = cpuinfo
+        .lines()
+        .find(|line| line.starts_with("model name") || line.starts_with("Model"))
+        .and_then(value)?;
+    let cores = cpuinfo
+        .lines()
+        .filter(|line| line.starts_with("processor"))
+        .count();
+    if cores > 0 {
+        Some(format!("{model} ({cores})"))
+    } else {
+        Some(model)
+    }
+}
+
+fn memory() -> Option<(u64, u64)> {
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    let field = |name: &str| -> Option<u64> {
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))?
+            .trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse()
+            .ok()
+    };
+    let total = field("MemTotal")?;
+    let available = field("MemAvailable").or_else(|| {
+        field("MemFree")?
+            .checked_add(field("Buffers")?)?
+            .checked_add(field("Cached")?)
+    })?;
+    Some((total.saturating_sub(available), total))
+}
+
+fn packages() -> Vec<(&'static str, usize)> {
+    packages_in(Path::new("/"))
+}
+
+/// Counts the installed packages of each known package manager, looking for
+/// their databases under `root`.
+///
+/// Only package managers whose databases are cheap to read are counted, e.g.
+/// not rpm, whose SQLite database is often tens of megabytes.
+fn packages_in(root: &Path) -> Vec<(&'static str, usize)> {
+    let count_lines = |path: &str, prefix: &str| -> Option<usize> {
+        let buf = fs::read_to_string(root.join(path)).ok()?;
+        Some(buf.lines().filter(|line| line.starts_with(prefix)).count())
+    };
+    let count_dirs = |path: &str| -> Option<usize> {
+        Some(
+            fs::read_dir(root.join(path))
+                .ok()?
+                .filter_map(Result::ok)
+                .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+                .count(),
+        )
+    };
+    iter::empty()
+        .chain(
+            count_lines("var/lib/dpkg/status", "Status: install ok installed").map(|n| ("dpkg", n)),
+        )
+        .chain(count_dirs("var/lib/pacman/local").map(|n| ("pacman", n)))
+        .chain(count_lines("lib/apk/db/installed", "P:").map(|n| ("apk", n)))
+        .filter(|&(_, n)| n > 0)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_distro_falls_back_to_id_like() {
+        let os_release = parse_os_release(
+            "NAME=\"Zzz Linux\"\nID=zzzlinux\nID_LIKE=\"zzz arch\"\nPRETTY_NAME=\"Zzz Linux\"\n",
+        );
+        let (name, distro) = match_distro(Some(&os_release), None).unwrap();
+        assert_eq!(name, "arch");
+        assert_eq!(distro.name(), "Arch");
+
+        let lsb_release = parse_os_release("DISTRIB_ID=Ubuntu\nDISTRIB_RELEASE=24.04\n");
+        let (_, distro) = match_distro(None, Some(&lsb_release)).unwrap();
+        assert_eq!(distro.name(), "Ubuntu");
+    }
+
+    #[test]
+    fn packages_counts_each_package_manager() {
+        let root = tempfile::tempdir().unwrap();
+        let write = |path: &str, buf: &[u8]| {
+            let path = root.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, buf).unwrap();
+        };
+        write(
+            "var/lib/dpkg/status",
+            b"Package: bash\nStatus: install ok installed\n\nPackage: vim\nStatus: deinstall ok config-files\n\nPackage: zsh\nStatus: install ok installed\n",
+        );
+        write("var/lib/pacman/local/ALPM_DB_VERSION", b"9\n");
+        write("var/lib/pacman/local/bash-5.2-1/desc", b"");
+        write(
+            "lib/apk/db/installed",
+            b"P:musl\nV:1.2.5\n\nP:busybox\nV:1.36.1\n",
+        );
+
+        assert_eq!(
+            packages_in(root.path()),
+            [("dpkg", 2), ("pacman", 1), ("apk", 2)]
+        );
+    }
+
+    #[test]
+    fn system_info_lines_skip_unknown_fields() {
+        let info = SystemInfo {
+            user: Some("user".to_owned()),
+            hostname: Some("host".to_owned()),
+            uptime: Some(Duration::from_secs(90_000)),
+            packages: vec![("dpkg", 2), ("apk", 3)],
+            memory: Some((1024, 4096)),
+            ..SystemInfo::default()
+        };
+        assert_eq!(
+            info.to_lines(),
+            [
+                "\x1b[1muser@host\x1b[22m",
+                "---------",
+                "\x1b[1mUptime\x1b[22m: 1 day, 1 hour",
+                "\x1b[1mPackages\x1b[22m: 2 (dpkg), 3 (apk)",
+                "\x1b[1mMemory\x1b[22m: 1MiB / 4MiB",
+            ]
+        );
+    }
+}
@@ -5,16 +5,15 @@ use std::fs;
 use std::io::{self, Write as _};
 use std::path::{PathBuf};
 use std::process::Command;
+use std::str::FromStr;
 use std::sync::OnceLock;
 use std::{env, fmt};
 
 use aho_corasick::AhoCorasick;
-use anyhow::{Context as _, Result};
+use anyhow::{anyhow, Context as _, Result};
 use indexmap::IndexMap;
 use itertools::Itertools as _;
 #[cfg(windows)]
-use anyhow::anyhow;
-#[cfg(windows)]
 use crate::utils::find_file;
 #[cfg(windows)]
 use std::path::Path;
@@ -31,7 +30,9 @@ use unicode_segmentation::UnicodeSegmentation as _;
 use which::which;
 use crate::ascii::{RawAsciiArt, RecoloredAsciiArt};
 use crate::color_util::{printc, NeofetchAsciiIndexedColor, PresetIndexedColor};
+use crate::distro_cache;
 use crate::distros::Distro;
+use crate::native;
 use crate::types::{AnsiMode, Backend};
 use crate::utils::{find_in_path, get_cache_path, input, process_command_status};
 
@@ -54,14 +55,31 @@ pub const NEOFETCH_COLOR_PATTERNS: [&str; 6] =
     ["${c1}", "${c2}", "${c3}", "${c4}", "${c5}", "${c6}"];
 pub static NEOFETCH_COLORS_AC: OnceLock<AhoCorasick> = OnceLock::new();
 pub const NEOFETCH_SCRIPT: &str = include_str!("../../../neofetch");
+pub const DEFAULT_DIAGONAL_ANGLE: f32 = 45.0;
+pub const DEFAULT_RADIAL_CENTER: (f32, f32) = (0.5, 0.5);
+
+/// Maximum number of similar distro names suggested for an unknown distro.
+const MAX_DISTRO_SUGGESTIONS: usize = 3;
 
-#[derive(Clone, Eq, PartialEq, Debug, AsRefStr, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Debug, AsRefStr, Deserialize, Serialize)]
 #[serde(tag = "mode")]
 #[serde(rename_all = "lowercase")]
 #[strum(serialize_all = "lowercase")]
 pub enum ColorAlignment {
     Horizontal,
     Vertical,
+    /// Spreads the colors along a direction, given in degrees clockwise from
+    /// left-to-right (0 is the same axis as vertical, 90 as horizontal).
+    Diagonal {
+        angle: f32,
+    },
+    /// Spreads the colors outwards from a center point, given relative to the
+    /// ascii art size (`(0.5, 0.5)` is the middle).
+    Radial {
+        center: (f32, f32),
+    },
+    /// Maps neofetch ascii color slots to preset color indices. With
+    /// combined presets, slots are mapped to whole presets instead.
     Custom {
         #[serde(rename = "custom_colors")]
         #[serde(deserialize_with = "crate::utils::index_map_serde::deserialize")]
@@ -69,6 +87,85 @@ pub enum ColorAlignment {
     },
 }
 
+impl FromStr for ColorAlignment {
+    type Err = anyhow::Error;
+
+    /// Parses a color alignment from the command line.
+    ///
+    /// Custom alignments are written as `custom:SLOT=INDEX,...`, e.g.
+    /// `custom:1=0,2=1`, mapping neofetch ascii color slots to preset color
+    /// indices.
+    fn from_str(s: &str) -> Result<Self> {
+        let (mode, params) = match s.split_once(':') {
+            Some((mode, params)) => (mode, Some(params)),
+            None => (s, None),
+        };
+
+        match (mode, params) {
+            ("horizontal", None) => Ok(Self::Horizontal),
+            ("vertical", None) => Ok(Self::Vertical),
+            ("diagonal", None) => Ok(Self::Diagonal {
+                angle: DEFAULT_DIAGONAL_ANGLE,
+            }),
+            ("diagonal", Some(angle)) => {
+                let angle: f32 = angle
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("invalid angle {angle:?}"))?;
+                if !angle.is_finite() {
+                    return Err(anyhow!("angle should be finite"));
+                }
+                Ok(Self::Diagonal { angle })
+            },
+            ("radial", None) => Ok(Self::Radial {
+                center: DEFAULT_RADIAL_CENTER,
+            }),
+            ("radial", Some(center)) => {
+                let (x, y) = center
+                    .split_once(',')
+                    .with_context(|| format!("{center:?} should be in X,Y format"))?;
+                let parse = |v: &str| -> Result<f32> {
+                    let v: f32 = v
+                        .trim()
+                        .parse()
+                        .with_context(|| format!("invalid center coordinate {v:?}"))?;
+                    if !(0.0..=1.0).contains(&v) {
+                        return Err(anyhow!("center coordinates should be between 0 and 1"));
+                    }
+                    Ok(v)
+                };
+                Ok(Self::Radial {
+                    center: (parse(x)?, parse(y)?),
+                })
+            },
+            ("custom", Some(params)) => {
+                let colors = params
+                    .split(',')
+                    .map(|pair| {
+                        let (ai, pi) = pair
+                            .split_once('=')
+                            .with_context(|| format!("{pair:?} should be in SLOT=INDEX format"))?;
+                        let ai: NeofetchAsciiIndexedColor = ai
+                            .trim()
+                            .parse()
+                            .with_context(|| format!("invalid ascii color slot {ai:?}"))?;
+                        let pi: PresetIndexedColor = pi
+                            .trim()
+                            .parse()
+                            .with_context(|| format!("invalid preset color index {pi:?}"))?;
+                        Ok((ai, pi))
+                    })
+                    .collect::<Result<_>>()?;
+                Ok(Self::Custom { colors })
+            },
+            _ => Err(anyhow!(
+                "COLOR_ALIGN should be one of \
+                 {{horizontal,vertical,diagonal[:ANGLE],radial[:X,Y],custom:SLOT=INDEX,...}}"
+            )),
+        }
+    }
+}
+
 /// Asks the user to provide an input among a list of options.
 pub fn literal_input<'a, S1, S2>(
     prompt: S1,
@@ -232,26 +329,30 @@ pub fn macchina_path() -> Result<Option<PathBuf>> {
 
 /// Gets the distro ascii of the current distro. Or if distro is specified, get
 /// the specific distro's ascii art instead.
+///
+/// Also returns the matched [`Distro`], unless the ascii art comes from
+/// neofetch.
 #[tracing::instrument(level = "debug")]
-pub fn get_distro_ascii<S>(distro: Option<S>, backend: Backend) -> Result<RawAsciiArt>
+pub fn get_distro_ascii<S>(
+    distro: Option<S>,
+    backend: Backend,
+) -> Result<(RawAsciiArt, Option<Distro>)>
 where
     S: AsRef<str> + fmt::Debug,
 {
-    let distro: Cow<_> = if let Some(distro) = distro.as_ref() {
-        distro.as_ref().into()
+    let (distro, variant): (Cow<_>, _) = if let Some(distro) = distro.as_ref() {
+        let variant =
+            Distro::detect(distro).with_context(|| unknown_distro_message(distro.as_ref()))?;
+        (distro.as_ref().into(), Some(variant))
     } else {
-        get_distro_name(backend)
-            .context("failed to get distro name")?
-            .into()
+        let (name, variant) = detect_distro(backend).context("failed to detect distro")?;
+        (name.into(), variant)
     };
     debug!(%distro, "distro name");
 
     // Try new codegen-based detection method
-    if let Some(distro) = Distro::detect(&distro) {
-        let asc = distro.ascii_art().to_owned();
-        let fg = ascii_foreground(&distro);
-
-        return Ok(RawAsciiArt { asc, fg });
+    if let Some(distro) = variant {
+        return Ok((RawAsciiArt::from_distro(&distro), Some(distro)));
     }
 
     debug!(%distro, "could not find a match for distro; falling back to neofetch");
@@ -264,28 +365,59 @@ where
     // printf
     let asc = asc.replace(r"\\", r"\");
 
-    Ok(RawAsciiArt {
-        asc,
-        fg: Vec::new(),
-    })
+    Ok((
+        RawAsciiArt {
+            asc,
+            fg: Vec::new(),
+        },
+        None,
+    ))
 }
 
 #[tracing::instrument(level = "debug", skip(asc), fields(asc.w = asc.w, asc.h = asc.h))]
 pub fn run(asc: RecoloredAsciiArt, backend: Backend, args: Option<&Vec<String>>) -> Result<()> {
-    let asc = asc.lines.join("\n");
+    run_backend(asc, backend, args, false)?;
+
+    Ok(())
+}
+
+/// Runs the backend like [`run`], but captures the colored output instead of
+/// writing it to stdout.
+#[tracing::instrument(level = "debug", skip(asc), fields(asc.w = asc.w, asc.h = asc.h))]
+pub fn run_captured(
+    asc: RecoloredAsciiArt,
+    backend: Backend,
+    args: Option<&Vec<String>>,
+) -> Result<String> {
+    run_backend(asc, backend, args, true)?
+        .with_context(|| format!("backend {backend:?} did not return the captured output"))
+}
 
+fn run_backend(
+    asc: RecoloredAsciiArt,
+    backend: Backend,
+    args: Option<&Vec<String>>,
+    capture: bool,
+) -> Result<Option<String>> {
     match backend {
-        Backend::Neofetch => run_neofetch(asc, args).context("failed to run neofetch")?,
-        Backend::Fastfetch => run_fastfetch(asc, args).context("failed to run fastfetch")?,
+        Backend::Neofetch => {
+            run_neofetch(asc.lines.join("\n"), args, capture).context("failed to run neofetch")
+        },
+        Backend::Fastfetch => {
+            run_fastfetch(asc.lines.join("\n"), args, capture).context("failed to run fastfetch")
+        },
         #[cfg(feature = "macchina")]
-        Backend::Macchina => run_macchina(asc, args).context("failed to run macchina")?,
+        Backend::Macchina => {
+            run_macchina(asc.lines.join("\n"), args, capture).context("failed to run macchina")
+        },
+        Backend::Native => {
+            native::run_native(asc, args, capture).context("failed to run native backend")
+        },
     }
-
-    Ok(())
 }
 
 /// Gets distro ascii width and height, ignoring color code.
-pub fn ascii_size<S>(asc: S) -> Result<(u8, u8)>
+pub fn ascii_size<S>(asc: S) -> Result<(u16, u16)>
 where
     S: AsRef<str>,
 {
@@ -312,17 +444,17 @@ where
         .map(|line| line.graphemes(true).count())
         .max()
         .expect("line iterator should not be empty");
-    let width: u8 = width.try_into().with_context(|| {
+    let width: u16 = width.try_into().with_context(|| {
         format!(
             "`asc` should not have more than {limit} characters per line",
-            limit = u8::MAX
+            limit = u16::MAX
         )
     })?;
     let height = asc.lines().count();
-    let height: u8 = height.try_into().with_context(|| {
+    let height: u16 = height.try_into().with_context(|| {
         format!(
             "`asc` should not have more than {limit} lines",
-            limit = u8::MAX
+            limit = u16::MAX
         )
     })?;
 
@@ -429,6 +561,18 @@ pub fn fastfetch_path() -> Result<PathBuf> {
     which("fastfetch").context("fastfetch command not found")
 }
 
+/// Gets the backend used when none is configured: fastfetch if it is
+/// installed, or else neofetch.
+///
+/// This looks for fastfetch in `PATH`, so only call it when needed.
+pub fn default_backend() -> Backend {
+    if fastfetch_path().is_ok() {
+        Backend::Fastfetch
+    } else {
+        Backend::Neofetch
+    }
+}
+
 fn make_fastfetch_command<S>(args: &[S]) -> Result<Command>
 where
     S: AsRef<OsStr>,
@@ -481,6 +625,46 @@ where
     Ok(command)
 }
 
+/// Describes a distro name that matches no distro, suggesting similar names.
+fn unknown_distro_message(name: &str) -> String {
+    let similar = Distro::similar(name)
+        .into_iter()
+        .take(MAX_DISTRO_SUGGESTIONS)
+        .map(|(similar, _)| similar)
+        .join(", ");
+    if similar.is_empty() {
+        format!("unknown distro {name:?}; see `hyfetch list distros` for known distros")
+    } else {
+        format!("unknown distro {name:?}, did you mean: {similar}?")
+    }
+}
+
+/// Gets the distro name and the matching [`Distro`].
+///
+/// The os-release and lsb-release files are read first. Otherwise, the name
+/// is taken from the backend, and the result is cached as running the backend
+/// is slow; see [`distro_cache`].
+#[tracing::instrument(level = "debug")]
+fn detect_distro(backend: Backend) -> Result<(String, Option<Distro>)> {
+    match native::detect_distro() {
+        Ok(Some((name, distro))) => return Ok((name, Some(distro))),
+        Ok(None) => debug!("no distro matched the release files"),
+        Err(err) => debug!(?err, "failed to detect distro from release files"),
+    }
+
+    if let Some((name, variant)) = distro_cache::load(backend) {
+        debug!(%name, ?variant, "using cached distro");
+        return Ok((name, variant));
+    }
+
+    let name = get_distro_name(backend).context("failed to get distro name")?;
+    let variant = Distro::detect(&name);
+    if let Err(err) = distro_cache::store(backend, &name, variant.as_ref()) {
+        debug!(?err, "failed to cache distro");
+    }
+    Ok((name, variant))
+}
+
 #[tracing::instrument(level = "debug")]
 pub(crate) fn get_distro_name(backend: Backend) -> Result<String> {
     match backend {
@@ -565,12 +749,19 @@ pub(crate) fn get_distro_name(backend: Backend) -> Result<String> {
                 })
                 .context("failed to get distro name from macchina")
         },
+        Backend::Native => {
+            native::get_distro_name().context("failed to get distro name from os-release")
+        },
     }
 }
 
 /// Runs neofetch with custom ascii art.
 #[tracing::instrument(level = "debug", skip(asc))]
-fn run_neofetch(asc: String, args: Option<&Vec<String>>) -> Result<()> {
+fn run_neofetch(
+    asc: String,
+    args: Option<&Vec<String>>,
+    capture: bool,
+) -> Result<Option<String>> {
     // Escape backslashes here because backslashes are escaped in neofetch for
     // printf
     let asc = asc.replace('\\', r"\\");
@@ -595,6 +786,10 @@ fn run_neofetch(asc: String, args: Option<&Vec<String>>) -> Result<()> {
             OsStr::new(&asc_file_path).into(),
             OsStr::new("--ascii_colors").into(),
         ];
+        if capture {
+            // Keep colors and ascii art even though stdout is not a terminal
+            v.push(OsStr::new("--stdout=off").into());
+        }
         if let Some(args) = args {
             v.extend(args.iter().map(|arg| OsStr::new(arg).into()));
         }
@@ -604,17 +799,31 @@ fn run_neofetch(asc: String, args: Option<&Vec<String>>) -> Result<()> {
 
     debug!(?command, "neofetch command");
 
+    if capture {
+        let output = command
+            .output()
+            .context("failed to execute neofetch command as child process")?;
+        process_command_status(&output.status).context("neofetch command exited with error")?;
+        let out = String::from_utf8(output.stdout)
+            .context("failed to process neofetch output as it contains invalid UTF-8")?;
+        return Ok(Some(out));
+    }
+
     let status = command
         .status()
         .context("failed to execute neofetch command as child process")?;
     process_command_status(&status).context("neofetch command exited with error")?;
 
-    Ok(())
+    Ok(None)
 }
 
 /// Runs fastfetch with custom ascii art.
 #[tracing::instrument(level = "debug", skip(asc))]
-fn run_fastfetch(asc: String, args: Option<&Vec<String>>) -> Result<()> {
+fn run_fastfetch(
+    asc: String,
+    args: Option<&Vec<String>>,
+    capture: bool,
+) -> Result<Option<String>> {
     // Write ascii art to temp file
     let asc_file_path = {
         let mut temp_file = tempfile::Builder::new()
@@ -633,6 +842,10 @@ fn run_fastfetch(asc: String, args: Option<&Vec<String>>) -> Result<()> {
             OsStr::new("--file-raw").into(),
             OsStr::new(&asc_file_path).into(),
         ];
+        if capture {
+            // Keep colors even though stdout is not a terminal
+            v.extend([OsStr::new("--pipe").into(), OsStr::new("false").into()]);
+        }
         if let Some(args) = args {
             v.extend(args.iter().map(|arg| OsStr::new(arg).into()));
         }
@@ -642,18 +855,32 @@ fn run_fastfetch(asc: String, args: Option<&Vec<String>>) -> Result<()> {
 
     debug!(?command, "fastfetch command");
 
+    if capture {
+        let output = command
+            .output()
+            .context("failed to execute fastfetch command as child process")?;
+        process_command_status(&output.status).context("fastfetch command exited with error")?;
+        let out = String::from_utf8(output.stdout)
+            .context("failed to process fastfetch output as it contains invalid UTF-8")?;
+        return Ok(Some(out));
+    }
+
     let status = command
         .status()
         .context("failed to execute fastfetch command as child process")?;
     process_command_status(&status).context("fastfetch command exited with error")?;
 
-    Ok(())
+    Ok(None)
 }
 
 /// Runs macchina with custom ascii art.
 #[cfg(feature = "macchina")]
 #[tracing::instrument(level = "debug", skip(asc))]
-fn run_macchina(asc: String, args: Option<&Vec<String>>) -> Result<()> {
+fn run_macchina(
+    asc: String,
+    args: Option<&Vec<String>>,
+    capture: bool,
+) -> Result<Option<String>> {
     // Write ascii art to temp file
     let asc_file_path = {
         let mut temp_file = tempfile::Builder::new()
@@ -709,17 +936,27 @@ fn run_macchina(asc: String, args: Option<&Vec<String>>) -> Result<()> {
 
     debug!(?command, "macchina command");
 
+    if capture {
+        let output = command
+            .output()
+            .context("failed to execute macchina command as child process")?;
+        process_command_status(&output.status).context("macchina command exited with error")?;
+        let out = String::from_utf8(output.stdout)
+            .context("failed to process macchina output as it contains invalid UTF-8")?;
+        return Ok(Some(out));
+    }
+
     let status = command
         .status()
         .context("failed to execute macchina command as child process")?;
     process_command_status(&status).context("macchina command exited with error")?;
 
-    Ok(())
+    Ok(None)
 }
 
 /// Gets the color indices that should be considered as foreground, for a
 /// particular distro's ascii art.
-fn ascii_foreground(distro: &Distro) -> Vec<NeofetchAsciiIndexedColor> {
+pub fn ascii_foreground(distro: &Distro) -> Vec<NeofetchAsciiIndexedColor> {
     let fg: Vec<u8> = match distro {
         Distro::Anarchy => vec![2],
         Distro::Android => vec![2],
@@ -782,3 +1019,67 @@ fn ascii_foreground(distro: &Distro) -> Vec<NeofetchAsciiIndexedColor> {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parse_position_color_alignments() {
+        assert_eq!(
+            "diagonal".parse::<ColorAlignment>().unwrap(),
+            ColorAlignment::Diagonal {
+                angle: DEFAULT_DIAGONAL_ANGLE
+            }
+        );
+        assert_eq!(
+            "diagonal:-30".parse::<ColorAlignment>().unwrap(),
+            ColorAlignment::Diagonal { angle: -30.0 }
+        );
+        assert_eq!(
+            "radial".parse::<ColorAlignment>().unwrap(),
+            ColorAlignment::Radial {
+                center: DEFAULT_RADIAL_CENTER
+            }
+        );
+        assert_eq!(
+            "radial:0.25, 1".parse::<ColorAlignment>().unwrap(),
+            ColorAlignment::Radial {
+                center: (0.25, 1.0)
+            }
+        );
+        for invalid in ["diagonal:inf", "diagonal:up", "radial:0.5", "radial:2,0"] {
+            assert!(invalid.parse::<ColorAlignment>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn color_alignment_serde_round_trip() {
+        let custom = "custom:1=0,2=1".parse::<ColorAlignment>().unwrap();
+        for (color_align, value) in [
+            (ColorAlignment::Horizontal, json!({"mode": "horizontal"})),
+            (
+                ColorAlignment::Diagonal { angle: 30.0 },
+                json!({"mode": "diagonal", "angle": 30.0}),
+            ),
+            (
+                ColorAlignment::Radial {
+                    center: (0.25, 0.5),
+                },
+                json!({"mode": "radial", "center": [0.25, 0.5]}),
+            ),
+            (
+                custom,
+                json!({"mode": "custom", "custom_colors": {"1": 0, "2": 1}}),
+            ),
+        ] {
+            assert_eq!(serde_json::to_value(&color_align).unwrap(), value);
+            assert_eq!(
+                serde_json::from_value::<ColorAlignment>(value).unwrap(),
+                color_align
+            );
+        }
+    }
+}
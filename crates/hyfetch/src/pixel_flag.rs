@@ -0,0 +1,85 @@
+use std::num::NonZeroU16;
+use std::str::FromStr;
+
+use anyhow::{Context as _, Error, Result};
+
+use crate::ascii::RecoloredAsciiArt;
+use crate::color_util::{color, ForegroundBackground, ToAnsiString as _};
+use crate::presets::ColorProfile;
+use crate::types::AnsiMode;
+
+/// Size of a pixel flag, in terminal cells.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct FlagSize {
+    pub w: NonZeroU16,
+    pub h: NonZeroU16,
+}
+
+impl FromStr for FlagSize {
+    type Err = Error;
+
+    /// Parses a size such as `32x8`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (w, h) = s
+            .split_once('x')
+            .context("SIZE should be in the form COLUMNSxROWS, e.g. 32x8")?;
+        Ok(Self {
+            w: w.parse()
+                .with_context(|| format!("flag width {w:?} should be a positive number"))?,
+            h: h.parse()
+                .with_context(|| format!("flag height {h:?} should be a positive number"))?,
+        })
+    }
+}
+
+/// Draws the color profile as a flag of horizontal stripes.
+///
+/// Each cell is an upper half block (`▀`), with the foreground color for the
+/// top half and the background color for the bottom half, so the flag has
+/// twice as many rows of pixels as terminal rows.
+pub fn pixel_flag(
+    color_profile: &ColorProfile,
+    size: FlagSize,
+    color_mode: AnsiMode,
+) -> Result<RecoloredAsciiArt> {
+    let FlagSize { w, h } = size;
+    let pixel_h = h
+        .checked_mul(NonZeroU16::new(2).unwrap())
+        .with_context(|| format!("flag height {h} is too large"))?;
+    let ColorProfile { colors, .. } = color_profile
+        .with_length(pixel_h)
+        .with_context(|| format!("failed to spread color profile to length {pixel_h}"))?;
+
+    let reset = color("&~&*", color_mode).expect("color reset should not be invalid");
+    let blocks = "▀".repeat(w.get().into());
+    let lines = colors
+        .chunks_exact(2)
+        .map(|pair| {
+            let top = pair[0].to_ansi_string(color_mode, ForegroundBackground::Foreground);
+            let bottom = pair[1].to_ansi_string(color_mode, ForegroundBackground::Background);
+            format!("{top}{bottom}{blocks}{reset}")
+        })
+        .collect();
+
+    Ok(RecoloredAsciiArt {
+        lines,
+        w: w.get(),
+        h: h.get(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::presets::Preset;
+
+    #[test]
+    fn pixel_flag_has_two_stripes_per_row() {
+        let color_profile = Preset::Transgender.color_profile();
+        let flag = pixel_flag(&color_profile, "4x3".parse().unwrap(), AnsiMode::Rgb).unwrap();
+        assert_eq!((flag.w, flag.h), (4, 3));
+        assert_eq!(flag.lines.len(), 3);
+        // Blue over pink, at the top of the flag
+        assert!(flag.lines[0].starts_with("\x1b[38;2;85;205;253m\x1b[48;2;246;170;183m▀▀▀▀"));
+    }
+}
@@ -1,21 +1,27 @@
 use std::iter;
-use std::num::{NonZeroU8, NonZeroUsize};
+use std::num::{NonZeroU16, NonZeroUsize};
+use std::ops::{Add, Mul, Range};
 
 use anyhow::{anyhow, Context as _, Result};
-use indexmap::IndexSet;
+use enterpolation::bspline::BSpline;
+use enterpolation::{Curve as _, Generator as _};
+use indexmap::{IndexMap, IndexSet};
+use itertools::Itertools as _;
 use palette::num::ClampAssign as _;
-use palette::{IntoColorMut as _, LinSrgb, Okhsl, Srgb};
-use serde::{Deserialize, Serialize};
+use palette::{IntoColor as _, IntoColorMut as _, LinSrgb, Okhsl, Oklab, Srgb};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use strum::{AsRefStr, EnumCount, EnumString, VariantArray, VariantNames};
 use tracing::debug;
 use unicode_segmentation::UnicodeSegmentation as _;
 
 use crate::color_util::{ForegroundBackground, Lightness, ToAnsiString as _};
-use crate::types::{AnsiMode, TerminalTheme};
+use crate::types::{AnsiMode, Blend, TerminalTheme};
 
 #[derive(
     Copy,
     Clone,
+    Eq,
+    PartialEq,
     Hash,
     Debug,
     AsRefStr,
@@ -221,6 +227,10 @@ pub enum Preset {
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct ColorProfile {
     pub colors: Vec<Srgb<u8>>,
+    pub blend: Blend,
+    /// Ranges of `colors` that came from each of the combined color profiles,
+    /// see [`ColorProfile::combine`]
+    pub parts: Vec<Range<usize>>,
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -230,6 +240,32 @@ pub enum AssignLightness {
     ClampMin(Lightness),
 }
 
+/// A user-defined preset, as stored in the `custom_presets` section of the
+/// config file.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct CustomPreset {
+    /// Hex colors of the flag, from top to bottom
+    pub colors: Vec<String>,
+    /// Weights of each color, see [`ColorProfile::with_weights`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weights: Option<Vec<u16>>,
+}
+
+/// Either a built-in [`Preset`], the name of a [`CustomPreset`], or several
+/// presets combined with `+`, e.g. `transgender+lesbian`.
+///
+/// Built-in presets take precedence over custom presets with the same name.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum AnyPreset {
+    Builtin(Preset),
+    Custom(String),
+    /// Presets sharing the ascii art, see [`ColorProfile::combine`]
+    Combined {
+        name: String,
+        presets: Vec<AnyPreset>,
+    },
+}
+
 impl Preset {
     pub fn color_profile(&self) -> ColorProfile {
         (match self {
@@ -637,9 +673,219 @@ impl Preset {
     }
 }
 
+impl CustomPreset {
+    pub fn color_profile(&self) -> Result<ColorProfile> {
+        let color_profile = ColorProfile::from_hex_colors(self.colors.iter().collect())?;
+        let color_profile = match &self.weights {
+            Some(weights) => color_profile.with_weights(weights.clone())?,
+            None => color_profile,
+        };
+        if color_profile.colors.is_empty() {
+            return Err(anyhow!("custom preset should have at least one color"));
+        }
+        Ok(color_profile)
+    }
+}
+
+impl AnyPreset {
+    /// Iterates over all built-in presets, followed by the custom presets.
+    ///
+    /// Custom presets that are shadowed by a built-in preset (or by `random`)
+    /// are skipped.
+    pub fn all(
+        custom_presets: &IndexMap<String, CustomPreset>,
+    ) -> impl Iterator<Item = AnyPreset> + '_ {
+        <Preset as VariantArray>::VARIANTS
+            .iter()
+            .map(|&preset| Self::Builtin(preset))
+            .chain(custom_presets.keys().filter_map(|name| {
+                if name == "random" || name.parse::<Preset>().is_ok() {
+                    debug!(%name, "custom preset is shadowed by a built-in preset");
+                    None
+                } else {
+                    Some(Self::Custom(name.clone()))
+                }
+            }))
+    }
+
+    /// Finds a preset by name, among the built-in presets and the given custom
+    /// presets.
+    ///
+    /// `random` picks one of them at random.
+    pub fn from_name(name: &str, custom_presets: &IndexMap<String, CustomPreset>) -> Result<Self> {
+        if let Ok(preset) = name.parse() {
+            return Ok(Self::Builtin(preset));
+        }
+
+        if name.contains('+') && !custom_presets.contains_key(name) {
+            let presets = name
+                .split('+')
+                .map(|name| {
+                    Self::from_name(name, custom_presets)
+                        .with_context(|| format!("failed to find preset {name:?}"))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(Self::combined(presets));
+        }
+
+        if name == "random" {
+            let mut rng = fastrand::Rng::new();
+            return Ok(rng
+                .choice(Self::all(custom_presets).collect::<Vec<_>>())
+                .expect("preset iterator should not be empty"));
+        }
+
+        if custom_presets.contains_key(name) {
+            return Ok(Self::Custom(name.to_owned()));
+        }
+
+        Err(anyhow!(
+            "PRESET should be one of {{{presets}}}",
+            presets = Self::all(custom_presets)
+                .map(|preset| preset.as_ref().to_owned())
+                .chain(iter::once("random".to_owned()))
+                .join(",")
+        ))
+    }
+
+    /// Combines presets, named by joining their names with `+`.
+    pub fn combined(presets: Vec<AnyPreset>) -> Self {
+        Self::Combined {
+            name: presets.iter().map(AsRef::as_ref).join("+"),
+            presets,
+        }
+    }
+
+    pub fn color_profile(
+        &self,
+        custom_presets: &IndexMap<String, CustomPreset>,
+    ) -> Result<ColorProfile> {
+        match self {
+            Self::Builtin(preset) => Ok(preset.color_profile()),
+            Self::Combined { presets, .. } => {
+                let color_profiles = presets
+                    .iter()
+                    .map(|preset| preset.color_profile(custom_presets))
+                    .collect::<Result<_>>()?;
+                ColorProfile::combine(color_profiles)
+            },
+            Self::Custom(name) => custom_presets
+                .get(name)
+                .with_context(|| format!("custom preset {name:?} is not defined in config"))?
+                .color_profile()
+                .with_context(|| format!("failed to get color profile of custom preset {name:?}")),
+        }
+    }
+}
+
+impl AsRef<str> for AnyPreset {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Builtin(preset) => preset.as_ref(),
+            Self::Custom(name) | Self::Combined { name, .. } => name,
+        }
+    }
+}
+
+impl Serialize for AnyPreset {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_ref())
+    }
+}
+
+impl<'de> Deserialize<'de> for AnyPreset {
+    /// Deserializes a preset by name, without checking that custom presets
+    /// are defined; that is done by [`AnyPreset::from_name`].
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        fn parse(name: &str) -> AnyPreset {
+            match name.parse() {
+                Ok(preset) => AnyPreset::Builtin(preset),
+                Err(_) => AnyPreset::Custom(name.to_owned()),
+            }
+        }
+
+        let name = String::deserialize(deserializer)?;
+        if name.contains('+') {
+            Ok(Self::combined(name.split('+').map(parse).collect()))
+        } else {
+            Ok(parse(&name))
+        }
+    }
+}
+
+impl From<Preset> for AnyPreset {
+    fn from(preset: Preset) -> Self {
+        Self::Builtin(preset)
+    }
+}
+
 impl ColorProfile {
     pub fn new(colors: Vec<Srgb<u8>>) -> Self {
-        Self { colors }
+        Self {
+            colors,
+            blend: Blend::default(),
+            parts: Vec::new(),
+        }
+    }
+
+    /// Combines color profiles into one, keeping track of where each of them
+    /// is.
+    ///
+    /// When spread, each of the color profiles takes an equal share of the
+    /// length, e.g. the top and bottom halves of the ascii art for two
+    /// profiles aligned horizontally. A custom color alignment assigns whole
+    /// color profiles to color slots, instead of single colors.
+    pub fn combine(color_profiles: Vec<ColorProfile>) -> Result<Self> {
+        if color_profiles.is_empty() {
+            return Err(anyhow!("at least one color profile should be combined"));
+        }
+
+        let mut colors = Vec::new();
+        let mut parts = Vec::new();
+        for color_profile in color_profiles {
+            if color_profile.colors.is_empty() {
+                return Err(anyhow!("combined color profiles should not be empty"));
+            }
+            let start = colors.len();
+            colors.extend(color_profile.colors);
+            parts.push(start..colors.len());
+        }
+
+        Ok(Self {
+            colors,
+            blend: Blend::default(),
+            parts,
+        })
+    }
+
+    /// Gets the combined color profiles, see [`ColorProfile::combine`].
+    ///
+    /// Returns an empty list if this color profile is not combined.
+    pub fn parts(&self) -> Vec<ColorProfile> {
+        self.parts
+            .iter()
+            .map(|part| Self {
+                colors: Vec::from(&self.colors[part.clone()]),
+                blend: self.blend,
+                parts: Vec::new(),
+            })
+            .collect()
+    }
+
+    /// Creates a new color profile, with the colors blended the specified way
+    /// when spread.
+    pub fn with_blend(&self, blend: Blend) -> Self {
+        Self {
+            colors: self.colors.clone(),
+            blend,
+            parts: self.parts.clone(),
+        }
     }
 
     pub fn from_hex_colors<S>(hex_colors: Vec<S>) -> Result<Self>
@@ -660,7 +906,7 @@ impl ColorProfile {
     ///
     /// * `weights` - Weights of each color (`weights[i]` = how many times
     ///   `colors[i]` appears)
-    pub fn with_weights(&self, weights: Vec<u8>) -> Result<Self> {
+    pub fn with_weights(&self, weights: Vec<u16>) -> Result<Self> {
         if weights.len() != self.colors.len() {
             debug!(?weights, ?self.colors, "length mismatch between `weights` and `colors`");
             return Err(anyhow!(
@@ -674,17 +920,57 @@ impl ColorProfile {
             weighted_colors.extend(iter::repeat(self.colors[i]).take(usize::from(w)));
         }
 
-        Ok(Self::new(weighted_colors))
+        Ok(Self {
+            colors: weighted_colors,
+            blend: self.blend,
+            parts: Vec::new(),
+        })
     }
 
     /// Creates a new color profile, with the colors spread to the specified
     /// length.
-    pub fn with_length(&self, length: NonZeroU8) -> Result<Self> {
+    pub fn with_length(&self, length: NonZeroU16) -> Result<Self> {
+        if !self.parts.is_empty() {
+            return self.with_length_per_part(length);
+        }
+
+        match self.blend {
+            Blend::Stripes => {},
+            Blend::Linear => {
+                let colors: Vec<LinSrgb> = self.colors.iter().map(|c| c.into_linear()).collect();
+                let colors = gradient(colors, length)
+                    .into_iter()
+                    .map(Srgb::<u8>::from_linear)
+                    .collect();
+                return Ok(Self {
+                    colors,
+                    blend: self.blend,
+                    parts: Vec::new(),
+                });
+            },
+            Blend::Oklab => {
+                let colors: Vec<Oklab> = self
+                    .colors
+                    .iter()
+                    .map(|c| c.into_linear().into_color())
+                    .collect();
+                let colors = gradient(colors, length)
+                    .into_iter()
+                    .map(|c| Srgb::<u8>::from_linear(c.into_color()))
+                    .collect();
+                return Ok(Self {
+                    colors,
+                    blend: self.blend,
+                    parts: Vec::new(),
+                });
+            },
+        }
+
         let orig_len = self.colors.len();
         let orig_len: NonZeroUsize = orig_len.try_into().expect("`colors` should not be empty");
-        let orig_len: NonZeroU8 = orig_len
+        let orig_len: NonZeroU16 = orig_len
             .try_into()
-            .expect("`colors` should not have more than 255 elements");
+            .expect("`colors` should not have more than 65535 elements");
         // TODO: I believe weird things can happen because of this...
         // if length < orig_len {
         //     unimplemented!("compressing length of color profile not implemented");
@@ -720,6 +1006,45 @@ impl ColorProfile {
         self.with_weights(weights)
     }
 
+    /// Spreads each of the combined color profiles to an equal share of the
+    /// length.
+    fn with_length_per_part(&self, length: NonZeroU16) -> Result<Self> {
+        let parts = self.parts();
+        let num_parts = u16::try_from(parts.len()).context("too many combined color profiles")?;
+        let share = length.get().div_euclid(num_parts);
+        let extras = usize::from(length.get().rem_euclid(num_parts));
+
+        let mut colors = Vec::new();
+        let mut ranges = Vec::new();
+        for (i, part) in parts.iter().enumerate() {
+            // The first parts get one more color, if the length doesn't divide
+            // evenly
+            let part_length = if i < extras {
+                share.checked_add(1).unwrap()
+            } else {
+                share
+            };
+            let Some(part_length) = NonZeroU16::new(part_length) else {
+                continue;
+            };
+            let start = colors.len();
+            colors.extend(
+                part.with_length(part_length)
+                    .with_context(|| {
+                        format!("failed to spread color profile to length {part_length}")
+                    })?
+                    .colors,
+            );
+            ranges.push(start..colors.len());
+        }
+
+        Ok(Self {
+            colors,
+            blend: self.blend,
+            parts: ranges,
+        })
+    }
+
     /// Colors a text.
     ///
     /// # Arguments
@@ -741,13 +1066,13 @@ impl ColorProfile {
 
         let txt: Vec<&str> = txt.graphemes(true).collect();
 
-        let ColorProfile { colors } = {
+        let ColorProfile { colors, .. } = {
             let length = txt.len();
             let length: NonZeroUsize = length.try_into().context("`txt` should not be empty")?;
-            let length: NonZeroU8 = length.try_into().with_context(|| {
+            let length: NonZeroU16 = length.try_into().with_context(|| {
                 format!(
                     "`txt` should not have more than {limit} characters",
-                    limit = u8::MAX
+                    limit = u16::MAX
                 )
             })?;
             self.with_length(length)
@@ -791,6 +1116,8 @@ impl ColorProfile {
 
         Self {
             colors: rgb_u8_colors,
+            blend: self.blend,
+            parts: self.parts.clone(),
         }
     }
 
@@ -825,6 +1152,8 @@ impl ColorProfile {
 
         Self {
             colors: rgb_u8_colors,
+            blend: self.blend,
+            parts: self.parts.clone(),
         }
     }
 
@@ -841,6 +1170,95 @@ impl ColorProfile {
     pub fn unique_colors(&self) -> Self {
         let unique_colors: IndexSet<[u8; 3]> = self.colors.iter().map(|&c| c.into()).collect();
         let unique_colors: Vec<Srgb<u8>> = unique_colors.into_iter().map(|c| c.into()).collect();
-        Self::new(unique_colors)
+        Self {
+            colors: unique_colors,
+            blend: self.blend,
+            parts: Vec::new(),
+        }
+    }
+}
+
+/// Interpolates a smooth gradient of `length` colors through `colors`.
+fn gradient<C>(colors: Vec<C>, length: NonZeroU16) -> Vec<C>
+where
+    C: Copy + Add<Output = C> + Mul<f32, Output = C> + Default,
+{
+    if colors.len() < 2 {
+        let color = *colors.first().expect("`colors` should not be empty");
+        return vec![color; usize::from(length.get())];
+    }
+
+    let spline = BSpline::builder()
+        .clamped()
+        .elements(colors)
+        .equidistant::<f32>()
+        .degree(1)
+        .normalized()
+        .constant::<2>()
+        .build()
+        .expect("building spline should not fail");
+    let [dmin, dmax] = spline.domain();
+    let last = f32::from(length.get().checked_sub(1).unwrap());
+    (0..length.get())
+        .map(|i| {
+            let t = if last > 0.0 { f32::from(i) / last } else { 0.5 };
+            spline.gen(dmin + t * (dmax - dmin))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combined_profiles_share_length_evenly() {
+        let combined = ColorProfile::combine(vec![
+            Preset::Transgender.color_profile(),
+            Preset::Lesbian.color_profile(),
+        ])
+        .unwrap();
+        let spread = combined.with_length(NonZeroU16::new(11).unwrap()).unwrap();
+        assert_eq!(spread.parts, [0..6, 6..11]);
+        assert_eq!(
+            spread.colors[..6],
+            Preset::Transgender
+                .color_profile()
+                .with_length(NonZeroU16::new(6).unwrap())
+                .unwrap()
+                .colors
+        );
+    }
+
+    #[test]
+    fn blend_interpolates_between_colors() {
+        let black_white = ColorProfile::from_hex_colors(vec!["#000000", "#FFFFFF"]).unwrap();
+        let length = NonZeroU16::new(3).unwrap();
+        let spread = |blend| {
+            black_white
+                .with_blend(blend)
+                .with_length(length)
+                .unwrap()
+                .colors
+        };
+        let (black, white) = (Srgb::new(0, 0, 0), Srgb::new(255, 255, 255));
+
+        // Stripes repeat the colors, widening the center one, as before blending
+        assert_eq!(spread(Blend::Stripes), [black, white, white]);
+        let rainbow = Preset::Rainbow.color_profile();
+        assert_eq!(
+            rainbow
+                .with_length(NonZeroU16::new(9).unwrap())
+                .unwrap()
+                .colors,
+            rainbow.with_weights(vec![2, 1, 1, 2, 1, 2]).unwrap().colors
+        );
+
+        // Gradients keep the end colors and interpolate in between
+        assert_eq!(
+            spread(Blend::Linear),
+            [black, Srgb::new(188, 188, 188), white]
+        );
+        assert_eq!(spread(Blend::Oklab), [black, Srgb::new(99, 99, 99), white]);
     }
 }
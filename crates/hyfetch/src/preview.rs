@@ -0,0 +1,131 @@
+use std::cmp;
+use std::fmt::Write as _;
+
+use anyhow::{Context as _, Result};
+use itertools::Itertools as _;
+
+use crate::ascii::NormalizedAsciiArt;
+use crate::models::Config;
+use crate::presets::AnyPreset;
+
+/// Width of the grid when the terminal size is unknown, e.g. when the output
+/// is redirected.
+pub const FALLBACK_WIDTH: u16 = 120;
+
+/// Number of spaces between tiles.
+const TILE_GAP: u16 = 2;
+
+/// Recolors the ascii art with each preset, and lays the results out in a
+/// grid that fits in `width` columns.
+///
+/// Each tile is labeled with the preset name. Colors are blended and
+/// lightened the same way as when running hyfetch with `config`.
+pub fn preview_presets(
+    asc: &NormalizedAsciiArt,
+    presets: &[AnyPreset],
+    config: &Config,
+    width: u16,
+) -> Result<String> {
+    let tile_w = usize::from(asc.w);
+    let tiles: Vec<Vec<String>> = presets
+        .iter()
+        .map(|preset| {
+            let color_profile = preset
+                .color_profile(&config.custom_presets)
+                .with_context(|| format!("failed to get color profile of preset {preset:?}"))?
+                .with_blend(config.blend)
+                .with_lightness_adaptive(config.lightness(), config.light_dark);
+            let mut lines = asc
+                .to_recolored(
+                    &config.color_align,
+                    &color_profile,
+                    config.style,
+                    config.mode,
+                    config.light_dark,
+                )
+                .with_context(|| format!("failed to recolor ascii with preset {preset:?}"))?
+                .lines;
+            let label: String = preset.as_ref().chars().take(tile_w).collect();
+            lines.push(format!("{label:^tile_w$}"));
+            Ok(lines)
+        })
+        .collect::<Result<_>>()?;
+
+    let tiles_per_row = cmp::max(1, width.div_euclid(asc.w.saturating_add(TILE_GAP)));
+    let gap = " ".repeat(TILE_GAP.into());
+    let mut buf = String::new();
+    for row in &tiles.into_iter().chunks(tiles_per_row.into()) {
+        let row: Vec<Vec<String>> = row.collect();
+        for i in 0..usize::from(asc.h).checked_add(1).unwrap() {
+            let line = row.iter().map(|lines| &*lines[i]).join(&gap);
+            writeln!(buf, "{line}").unwrap();
+        }
+        buf.push('\n');
+    }
+
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::ascii::RawAsciiArt;
+    use crate::presets::Preset;
+
+    /// Removes the color codes, leaving only the visible text.
+    fn strip_colors(s: &str) -> String {
+        let mut visible = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.by_ref().find(|&c| c == 'm');
+            } else {
+                visible.push(c);
+            }
+        }
+        visible
+    }
+
+    #[test]
+    fn preview_wraps_tiles_to_width() {
+        let line = "#".repeat(40);
+        let asc = RawAsciiArt {
+            asc: format!("${{c1}}{line}\n{line}"),
+            fg: Vec::new(),
+        }
+        .to_normalized()
+        .unwrap();
+        let config: Config = serde_json::from_value(json!({
+            "preset": "rainbow",
+            "mode": "rgb",
+            "light_dark": "dark",
+            "lightness": null,
+            "color_align": { "mode": "horizontal" },
+            "backend": "native",
+            "distro": null,
+            "pride_month_disable": false,
+        }))
+        .unwrap();
+        let presets = [Preset::Rainbow, Preset::Transgender, Preset::Lesbian].map(AnyPreset::from);
+
+        // Two 40 column tiles and a gap fit in 120 columns, but three don't
+        let preview = preview_presets(&asc, &presets, &config, FALLBACK_WIDTH).unwrap();
+        let lines: Vec<String> = preview.lines().map(strip_colors).collect();
+        let two = format!("{line}  {line}");
+        assert_eq!(
+            lines,
+            [
+                &*two,
+                &two,
+                &format!("{:^40}  {:^40}", "rainbow", "transgender"),
+                "",
+                &line,
+                &line,
+                &format!("{:^40}", "lesbian"),
+                "",
+            ]
+        );
+    }
+}
@@ -62,14 +62,14 @@ pub fn start_animation(color_mode: AnsiMode) -> Result<()> {
             .try_into()
             .expect("`NOTICE` height should fit in `u8`");
         let term_w_min = cmp::max(
-            u16::from(text_width)
+            text_width
                 .checked_add(TEXT_BORDER_WIDTH.checked_mul(2).unwrap())
                 .unwrap(),
             u16::from(notice_w)
                 .checked_add(NOTICE_BORDER_WIDTH.checked_mul(2).unwrap())
                 .unwrap(),
         );
-        let term_h_min = u16::from(text_height)
+        let term_h_min = text_height
             .checked_add(notice_h.into())
             .unwrap()
             .checked_add(VERTICAL_MARGIN.checked_mul(2).unwrap())
@@ -81,14 +81,14 @@ pub fn start_animation(color_mode: AnsiMode) -> Result<()> {
             let (text_width, text_height) =
                 ascii_size(text).expect("text ascii should have valid width and height");
             let term_w_min = cmp::max(
-                u16::from(text_width)
+                text_width
                     .checked_add(TEXT_BORDER_WIDTH.checked_mul(2).unwrap())
                     .unwrap(),
                 u16::from(notice_w)
                     .checked_add(NOTICE_BORDER_WIDTH.checked_mul(2).unwrap())
                     .unwrap(),
             );
-            let term_h_min = u16::from(text_height)
+            let term_h_min = text_height
                 .checked_add(notice_h.into())
                 .unwrap()
                 .checked_add(VERTICAL_MARGIN.checked_mul(2).unwrap())
@@ -110,18 +110,10 @@ pub fn start_animation(color_mode: AnsiMode) -> Result<()> {
         .try_into()
         .with_context(|| format!("terminal width should be at least {BLOCKS}"))?;
 
-    let text_start_y = h
-        .get()
-        .div_euclid(2)
-        .checked_sub((text_height / 2).into())
-        .unwrap();
-    let text_end_y = text_start_y.checked_add(text_height.into()).unwrap();
-    let text_start_x = w
-        .get()
-        .div_euclid(2)
-        .checked_sub((text_width / 2).into())
-        .unwrap();
-    let text_end_x = text_start_x.checked_add(text_width.into()).unwrap();
+    let text_start_y = h.get().div_euclid(2).checked_sub(text_height / 2).unwrap();
+    let text_end_y = text_start_y.checked_add(text_height).unwrap();
+    let text_start_x = w.get().div_euclid(2).checked_sub(text_width / 2).unwrap();
+    let text_end_x = text_start_x.checked_add(text_width).unwrap();
 
     let notice_start_x = w
         .get()
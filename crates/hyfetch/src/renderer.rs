@@ -0,0 +1,238 @@
+use anyhow::{Context as _, Result};
+use indexmap::IndexMap;
+
+use crate::ascii::{RawAsciiArt, RecoloredAsciiArt};
+use crate::color_util::Lightness;
+use crate::models::Config;
+use crate::neofetch_util::ColorAlignment;
+use crate::presets::{AnyPreset, ColorProfile, CustomPreset};
+use crate::types::{AnsiMode, Blend, LogoStyle, TerminalTheme};
+
+/// Recolors ascii art with a preset, the same way as the `hyfetch` binary.
+///
+/// This never panics on invalid input; errors such as an undefined custom
+/// preset, or a custom alignment using more colors than the preset has, are
+/// returned from [`Renderer::render`].
+///
+/// Defaults to true color on a dark terminal, horizontal stripes colored in
+/// the foreground, and the default lightness for the theme.
+///
+/// # Examples
+///
+/// ```
+/// use hyfetch::ascii::RawAsciiArt;
+/// use hyfetch::color_util::Lightness;
+/// use hyfetch::distros::Distro;
+/// use hyfetch::neofetch_util::ColorAlignment;
+/// use hyfetch::presets::Preset;
+/// use hyfetch::Renderer;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let distro = Distro::detect("arch").expect("arch should have ascii art");
+/// let asc = Renderer::new(Preset::Transgender)
+///     .lightness(Lightness::new(0.7)?)
+///     .align(ColorAlignment::Vertical)
+///     .render(&RawAsciiArt::from_distro(&distro))?;
+/// for line in asc.lines {
+///     println!("{line}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Renderer {
+    preset: AnyPreset,
+    custom_presets: IndexMap<String, CustomPreset>,
+    color_mode: AnsiMode,
+    theme: TerminalTheme,
+    lightness: Option<Lightness>,
+    blend: Blend,
+    style: LogoStyle,
+    color_align: ColorAlignment,
+}
+
+impl Renderer {
+    pub fn new<P>(preset: P) -> Self
+    where
+        P: Into<AnyPreset>,
+    {
+        Self {
+            preset: preset.into(),
+            custom_presets: IndexMap::new(),
+            color_mode: AnsiMode::Rgb,
+            theme: TerminalTheme::Dark,
+            lightness: None,
+            blend: Blend::default(),
+            style: LogoStyle::default(),
+            color_align: ColorAlignment::Horizontal,
+        }
+    }
+
+    /// Creates a renderer with the same settings as a hyfetch config.
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            preset: config.preset.clone(),
+            custom_presets: config.custom_presets.clone(),
+            color_mode: config.mode,
+            theme: config.light_dark,
+            lightness: config.lightness,
+            blend: config.blend,
+            style: config.style,
+            color_align: config.color_align.clone(),
+        }
+    }
+
+    /// Sets the custom presets that [`AnyPreset::Custom`] refers to.
+    pub fn custom_presets(mut self, custom_presets: IndexMap<String, CustomPreset>) -> Self {
+        self.custom_presets = custom_presets;
+        self
+    }
+
+    pub fn color_mode(mut self, color_mode: AnsiMode) -> Self {
+        self.color_mode = color_mode;
+        self
+    }
+
+    /// Sets the terminal theme, which decides whether colors are lightened or
+    /// darkened, and the color of foreground parts of the ascii art.
+    pub fn theme(mut self, theme: TerminalTheme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Sets the lightness that colors are clamped to: the minimum on a dark
+    /// terminal, or the maximum on a light terminal.
+    pub fn lightness(mut self, lightness: Lightness) -> Self {
+        self.lightness = Some(lightness);
+        self
+    }
+
+    pub fn blend(mut self, blend: Blend) -> Self {
+        self.blend = blend;
+        self
+    }
+
+    /// Sets whether the ascii art is drawn with foreground colors, background
+    /// colors, or both.
+    pub fn style(mut self, style: LogoStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn align(mut self, color_align: ColorAlignment) -> Self {
+        self.color_align = color_align;
+        self
+    }
+
+    /// Gets the color profile of the preset, blended and lightened.
+    pub fn color_profile(&self) -> Result<ColorProfile> {
+        let lightness = self
+            .lightness
+            .unwrap_or_else(|| Config::default_lightness(self.theme));
+        let color_profile = self
+            .preset
+            .color_profile(&self.custom_presets)
+            .with_context(|| format!("failed to get color profile of preset {:?}", self.preset))?
+            .with_blend(self.blend)
+            .with_lightness_adaptive(lightness, self.theme);
+        Ok(color_profile)
+    }
+
+    /// Recolors the ascii art.
+    pub fn render(&self, asc: &RawAsciiArt) -> Result<RecoloredAsciiArt> {
+        let color_profile = self.color_profile()?;
+        let asc = asc.to_normalized().context("failed to normalize ascii")?;
+        asc.to_recolored(
+            &self.color_align,
+            &color_profile,
+            self.style,
+            self.color_mode,
+            self.theme,
+        )
+        .context("failed to recolor ascii")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color_util::PresetIndexedColor;
+    use crate::presets::Preset;
+
+    #[test]
+    fn render_reports_invalid_input() {
+        let asc = RawAsciiArt {
+            asc: "${c1}hy\n${c2}fetch".to_owned(),
+            fg: Vec::new(),
+        };
+
+        let err = Renderer::new(AnyPreset::Custom("mine".to_owned()))
+            .render(&asc)
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("not defined"));
+
+        let colors = [(1, 0), (2, 9)]
+            .into_iter()
+            .map(|(ai, pi)| (ai.try_into().unwrap(), PresetIndexedColor::from(pi)))
+            .collect();
+        let err = Renderer::new(Preset::Rainbow)
+            .align(ColorAlignment::Custom { colors })
+            .render(&asc)
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("custom color index 9"));
+    }
+
+    #[test]
+    fn background_style_paints_every_cell() {
+        let raw = RawAsciiArt {
+            asc: "${c1}# #".to_owned(),
+            fg: Vec::new(),
+        };
+
+        let asc = Renderer::new(Preset::Rainbow)
+            .style(LogoStyle::Background)
+            .render(&raw)
+            .unwrap();
+        // Background color, then a contrasting gray for the characters
+        assert!(asc.lines[0].starts_with("\x1b[48;2;"));
+        assert!(asc.lines[0].contains("m\x1b[38;2;"));
+        assert!(asc.lines[0].contains("# #"));
+
+        let asc = Renderer::new(Preset::Rainbow)
+            .style(LogoStyle::Both)
+            .render(&raw)
+            .unwrap();
+        assert!(asc.lines[0].starts_with("\x1b[38;2;"));
+        assert!(asc.lines[0].contains("\x1b[49m#\x1b[48;2;"));
+    }
+
+    #[test]
+    fn position_alignments_spread_colors() {
+        let raw = RawAsciiArt {
+            asc: "${c1}#####\n#####\n#####".to_owned(),
+            fg: Vec::new(),
+        };
+        let render = |color_align: &str| {
+            Renderer::new(Preset::Rainbow)
+                .align(color_align.parse().unwrap())
+                .render(&raw)
+                .unwrap()
+                .lines
+        };
+
+        // Each cell of a vertical line has its own color
+        let vertical = render("vertical");
+        let colors: Vec<&str> = vertical[0].split('#').collect();
+        let (first, last) = (colors[0], colors[4]);
+
+        let diagonal = render("diagonal");
+        assert!(diagonal[0].starts_with(&format!("{first}#")));
+        assert!(diagonal[2].contains(&format!("{last}#\x1b[39m")));
+        assert_ne!(diagonal[0], diagonal[1]);
+
+        let radial = render("radial");
+        assert!(radial[0].starts_with(&format!("{last}#")));
+        assert!(radial[1].contains(&format!("{first}#")));
+        assert_eq!(radial[0], radial[2]);
+    }
+}
@@ -0,0 +1,875 @@
+//! Full-screen config wizard, driven by arrow keys in raw mode.
+
+use std::cmp;
+use std::io::{self, IsTerminal as _, Write as _};
+
+use anyhow::{Context as _, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{
+    self, BeginSynchronizedUpdate, EndSynchronizedUpdate, EnterAlternateScreen,
+    LeaveAlternateScreen,
+};
+use crossterm::{cursor, execute};
+use indexmap::IndexMap;
+use strum::VariantNames as _;
+use terminal_size::{terminal_size, Height, Width};
+
+use crate::ascii::NormalizedAsciiArt;
+use crate::color_util::{color, ForegroundBackground, Lightness, PresetIndexedColor};
+use crate::models::Config;
+#[cfg(feature = "macchina")]
+use crate::neofetch_util::macchina_path;
+use crate::neofetch_util::{
+    fastfetch_path, ColorAlignment, DEFAULT_DIAGONAL_ANGLE, DEFAULT_RADIAL_CENTER,
+};
+use crate::presets::{AnyPreset, ColorProfile, CustomPreset, Preset};
+use crate::types::{AnsiMode, Backend, LogoStyle, TerminalTheme};
+
+/// Color modes, in the order they are listed.
+const COLOR_MODES: [AnsiMode; 3] = [AnsiMode::Ansi16, AnsiMode::Ansi256, AnsiMode::Rgb];
+
+/// Themes, in the order they are listed.
+const THEMES: [TerminalTheme; 2] = [TerminalTheme::Light, TerminalTheme::Dark];
+
+/// Number of random arrangements offered besides the standard ones.
+const RANDOM_ARRANGEMENTS: usize = 4;
+
+/// Width of a flag in the preset grid.
+const FLAG_WIDTH: usize = 18;
+
+/// Width of the color bars and the lightness slider.
+const BAR_WIDTH: usize = 24;
+
+/// Number of columns between the logo and the panel.
+const GAP: usize = 4;
+
+/// Minimum width of the panel for it to be shown beside the logo.
+const MIN_PANEL_WIDTH: usize = 40;
+
+/// Choices made in the wizard.
+#[derive(Clone, Debug)]
+pub struct WizardChoices {
+    pub color_mode: AnsiMode,
+    pub theme: TerminalTheme,
+    pub preset: AnyPreset,
+    pub lightness: Lightness,
+    pub color_align: ColorAlignment,
+    pub backend: Backend,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Step {
+    ColorMode,
+    Theme,
+    Preset,
+    Lightness,
+    Arrangement,
+    Backend,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Backspace,
+    Cancel,
+    Char(char),
+    Other,
+}
+
+struct Wizard<'a> {
+    title: &'a str,
+    asc: &'a NormalizedAsciiArt,
+    custom_presets: &'a IndexMap<String, CustomPreset>,
+    presets: Vec<AnyPreset>,
+    steps: Vec<Step>,
+    step: usize,
+    color_mode: AnsiMode,
+    theme: TerminalTheme,
+    /// Presets matching the search, as indices into `presets`
+    filtered: Vec<usize>,
+    search: String,
+    preset_cursor: usize,
+    preset_scroll: usize,
+    preset: Option<AnyPreset>,
+    lightness: Option<f32>,
+    arrangements: Vec<(String, ColorAlignment)>,
+    arrangement_cursor: usize,
+    color_align: Option<ColorAlignment>,
+    backends: Vec<(Backend, String)>,
+    backend_cursor: usize,
+    rng: fastrand::Rng,
+}
+
+/// Restores the terminal when dropped, including on errors.
+struct RawScreen;
+
+/// Checks whether the full-screen wizard can be used.
+pub fn is_supported() -> bool {
+    io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+/// Runs the full-screen wizard.
+///
+/// Steps for a detected color mode or theme are skipped. Returns `None` if
+/// the wizard is cancelled with Ctrl-C.
+pub fn run_wizard(
+    title: &str,
+    asc: &NormalizedAsciiArt,
+    custom_presets: &IndexMap<String, CustomPreset>,
+    detected_mode: Option<AnsiMode>,
+    detected_theme: Option<TerminalTheme>,
+    backend: Backend,
+) -> Result<Option<WizardChoices>> {
+    let mut wizard = Wizard::new(
+        title,
+        asc,
+        custom_presets,
+        detected_mode,
+        detected_theme,
+        backend,
+    )?;
+
+    let _screen = RawScreen::enter().context("failed to set up terminal")?;
+    loop {
+        wizard.draw().context("failed to draw wizard")?;
+        let key = read_key().context("failed to read key")?;
+        if key == Key::Cancel {
+            return Ok(None);
+        }
+        if let Some(choices) = wizard.handle_key(key)? {
+            return Ok(Some(choices));
+        }
+    }
+}
+
+impl<'a> Wizard<'a> {
+    fn new(
+        title: &'a str,
+        asc: &'a NormalizedAsciiArt,
+        custom_presets: &'a IndexMap<String, CustomPreset>,
+        detected_mode: Option<AnsiMode>,
+        detected_theme: Option<TerminalTheme>,
+        backend: Backend,
+    ) -> Result<Self> {
+        let mut steps = Vec::new();
+        if detected_mode != Some(AnsiMode::Rgb) {
+            steps.push(Step::ColorMode);
+        }
+        if detected_theme.is_none() {
+            steps.push(Step::Theme);
+        }
+        steps.extend([
+            Step::Preset,
+            Step::Lightness,
+            Step::Arrangement,
+            Step::Backend,
+        ]);
+
+        let presets: Vec<AnyPreset> = AnyPreset::all(custom_presets).collect();
+        let backends = backend_descriptions().context("failed to describe backends")?;
+        let backend_cursor = backends
+            .iter()
+            .position(|&(b, _)| b == backend)
+            .unwrap_or(0);
+
+        Ok(Self {
+            title,
+            asc,
+            custom_presets,
+            filtered: (0..presets.len()).collect(),
+            presets,
+            steps,
+            step: 0,
+            color_mode: detected_mode.unwrap_or(AnsiMode::Rgb),
+            theme: detected_theme.unwrap_or(TerminalTheme::Dark),
+            search: String::new(),
+            preset_cursor: 0,
+            preset_scroll: 0,
+            preset: None,
+            lightness: None,
+            arrangements: Vec::new(),
+            arrangement_cursor: 0,
+            color_align: None,
+            backends,
+            backend_cursor,
+            rng: fastrand::Rng::new(),
+        })
+    }
+
+    fn current_step(&self) -> Step {
+        self.steps[self.step]
+    }
+
+    /// Preset to preview: the one under the cursor while choosing, or else
+    /// the chosen one.
+    fn current_preset(&self) -> AnyPreset {
+        if self.current_step() == Step::Preset {
+            if let Some(&i) = self.filtered.get(self.preset_cursor) {
+                return self.presets[i].clone();
+            }
+        }
+        self.preset
+            .clone()
+            .unwrap_or_else(|| Preset::Rainbow.into())
+    }
+
+    fn current_lightness(&self) -> Lightness {
+        self.lightness
+            .and_then(|lightness| Lightness::new(lightness).ok())
+            .unwrap_or_else(|| Config::default_lightness(self.theme))
+    }
+
+    fn current_color_align(&self) -> ColorAlignment {
+        if self.current_step() == Step::Arrangement {
+            if let Some((_, color_align)) = self.arrangements.get(self.arrangement_cursor) {
+                return color_align.clone();
+            }
+        }
+        self.color_align
+            .clone()
+            .unwrap_or(ColorAlignment::Horizontal)
+    }
+
+    fn color_profile(&self, preset: &AnyPreset) -> Result<ColorProfile> {
+        preset
+            .color_profile(self.custom_presets)
+            .with_context(|| format!("failed to get color profile of preset {preset:?}"))
+    }
+
+    /// Handles a key press, returning the choices once the last step is done.
+    fn handle_key(&mut self, key: Key) -> Result<Option<WizardChoices>> {
+        match (self.current_step(), key) {
+            (Step::ColorMode, Key::Up | Key::Down) => {
+                self.color_mode = cycle(&COLOR_MODES, self.color_mode, key == Key::Down);
+            },
+            (Step::Theme, Key::Up | Key::Down) => {
+                self.theme = cycle(&THEMES, self.theme, key == Key::Down);
+            },
+            (Step::Preset, Key::Char(c)) => {
+                self.search.push(c);
+                self.update_search();
+            },
+            (Step::Preset, Key::Backspace) if !self.search.is_empty() => {
+                self.search.pop();
+                self.update_search();
+            },
+            (Step::Preset, Key::Left) => {
+                self.preset_cursor = self.preset_cursor.saturating_sub(1);
+            },
+            (Step::Preset, Key::Right) => {
+                self.preset_cursor = cmp::min(
+                    self.preset_cursor.saturating_add(1),
+                    self.filtered.len().saturating_sub(1),
+                );
+            },
+            (Step::Preset, Key::Up) => {
+                self.preset_cursor = self.preset_cursor.saturating_sub(self.flags_per_row());
+            },
+            (Step::Preset, Key::Down) => {
+                self.preset_cursor = cmp::min(
+                    self.preset_cursor.saturating_add(self.flags_per_row()),
+                    self.filtered.len().saturating_sub(1),
+                );
+            },
+            (Step::Preset, Key::Enter) if self.filtered.is_empty() => {},
+            (Step::Lightness, Key::Left | Key::Right | Key::Up | Key::Down) => {
+                let delta = match key {
+                    Key::Left => -0.01,
+                    Key::Right => 0.01,
+                    Key::Down => -0.1,
+                    _ => 0.1,
+                };
+                let lightness = f32::from(self.current_lightness()) + delta;
+                // Round to whole percents, to avoid accumulating float errors
+                let lightness = (lightness * 100.0).round() / 100.0;
+                self.lightness = Some(lightness.clamp(Lightness::MIN, Lightness::MAX));
+            },
+            (Step::Arrangement, Key::Up) => {
+                self.arrangement_cursor = self.arrangement_cursor.saturating_sub(1);
+            },
+            (Step::Arrangement, Key::Down) => {
+                self.arrangement_cursor = cmp::min(
+                    self.arrangement_cursor.saturating_add(1),
+                    self.arrangements.len().saturating_sub(1),
+                );
+            },
+            (Step::Arrangement, Key::Char('r')) => {
+                self.roll_arrangements()?;
+            },
+            (Step::Backend, Key::Up) => {
+                self.backend_cursor = self.backend_cursor.saturating_sub(1);
+            },
+            (Step::Backend, Key::Down) => {
+                self.backend_cursor = cmp::min(
+                    self.backend_cursor.saturating_add(1),
+                    self.backends.len().saturating_sub(1),
+                );
+            },
+            (_, Key::Backspace) => {
+                self.step = self.step.saturating_sub(1);
+            },
+            (step, Key::Enter) => {
+                match step {
+                    Step::Preset => {
+                        self.preset = Some(self.current_preset());
+                        self.roll_arrangements()?;
+                    },
+                    Step::Arrangement => {
+                        self.color_align = Some(self.current_color_align());
+                    },
+                    Step::Backend => return self.choices().map(Some),
+                    _ => {},
+                }
+                self.step = self.step.saturating_add(1);
+            },
+            _ => {},
+        }
+        Ok(None)
+    }
+
+    fn update_search(&mut self) {
+        let search = self.search.to_lowercase();
+        self.filtered = self
+            .presets
+            .iter()
+            .enumerate()
+            .filter(|(_, preset)| preset.as_ref().to_lowercase().contains(&search))
+            .map(|(i, _)| i)
+            .collect();
+        self.preset_cursor = 0;
+        self.preset_scroll = 0;
+    }
+
+    /// Replaces the random arrangements, keeping the standard ones.
+    fn roll_arrangements(&mut self) -> Result<()> {
+        let mut arrangements = vec![
+            ("horizontal".to_owned(), ColorAlignment::Horizontal),
+            ("vertical".to_owned(), ColorAlignment::Vertical),
+            (
+                "diagonal".to_owned(),
+                ColorAlignment::Diagonal {
+                    angle: DEFAULT_DIAGONAL_ANGLE,
+                },
+            ),
+            (
+                "radial".to_owned(),
+                ColorAlignment::Radial {
+                    center: DEFAULT_RADIAL_CENTER,
+                },
+            ),
+        ];
+
+        let slots = self.asc.color_slots();
+        let num_colors = self
+            .color_profile(&self.current_preset())?
+            .unique_colors()
+            .colors
+            .len();
+        let num_colors = u8::try_from(num_colors).unwrap_or(u8::MAX);
+        if !slots.is_empty() && num_colors > 1 {
+            for i in 0..RANDOM_ARRANGEMENTS {
+                let colors = slots
+                    .iter()
+                    .map(|&slot| (slot, PresetIndexedColor::from(self.rng.u8(..num_colors))))
+                    .collect();
+                arrangements.push((format!("random{i}"), ColorAlignment::Custom { colors }));
+            }
+        }
+
+        self.arrangements = arrangements;
+        self.arrangement_cursor = cmp::min(
+            self.arrangement_cursor,
+            self.arrangements.len().saturating_sub(1),
+        );
+        Ok(())
+    }
+
+    fn choices(&self) -> Result<WizardChoices> {
+        Ok(WizardChoices {
+            color_mode: self.color_mode,
+            theme: self.theme,
+            preset: self.current_preset(),
+            lightness: self.current_lightness(),
+            color_align: self.current_color_align(),
+            backend: self.backends[self.backend_cursor].0,
+        })
+    }
+
+    fn flags_per_row(&self) -> usize {
+        let (term_w, _) = term_size();
+        let panel_w = self.layout(term_w).1;
+        cmp::max(
+            1,
+            panel_w
+                .saturating_add(2)
+                .div_euclid(FLAG_WIDTH.saturating_add(2)),
+        )
+    }
+
+    /// Gets whether the panel goes beside the logo, and the panel width.
+    fn layout(&self, term_w: usize) -> (bool, usize) {
+        let beside_w = term_w
+            .saturating_sub(usize::from(self.asc.w))
+            .saturating_sub(GAP);
+        if beside_w >= MIN_PANEL_WIDTH {
+            (true, beside_w)
+        } else {
+            (false, term_w)
+        }
+    }
+
+    fn draw(&mut self) -> Result<()> {
+        let (term_w, term_h) = term_size();
+        let (beside, panel_w) = self.layout(term_w);
+
+        let mut header = vec![
+            color(self.title, self.color_mode).context("failed to color title")?,
+            self.summary()?,
+            String::new(),
+        ];
+        let footer = [
+            String::new(),
+            color(self.help(), self.color_mode).context("failed to color help")?,
+        ];
+        let body_h = term_h
+            .saturating_sub(header.len())
+            .saturating_sub(footer.len());
+
+        let logo = self.logo()?;
+        let body = if beside {
+            let panel = self.panel(panel_w, body_h)?;
+            let blank = " ".repeat(usize::from(self.asc.w));
+            (0..cmp::max(logo.len(), panel.len()))
+                .take(body_h)
+                .map(|i| {
+                    format!(
+                        "{logo}{gap}{panel}",
+                        logo = logo.get(i).unwrap_or(&blank),
+                        gap = " ".repeat(GAP),
+                        panel = panel.get(i).map_or("", String::as_str)
+                    )
+                })
+                .collect()
+        } else {
+            let panel_h = body_h.saturating_sub(logo.len()).saturating_sub(1);
+            let mut body = logo;
+            body.push(String::new());
+            body.extend(self.panel(panel_w, panel_h)?);
+            body.truncate(body_h);
+            body
+        };
+
+        header.extend(body);
+        header.extend(footer);
+        let buf = header.join("\x1b[K\r\n");
+
+        let mut stdout = io::stdout().lock();
+        execute!(stdout, BeginSynchronizedUpdate).context("failed to begin synchronized update")?;
+        write!(stdout, "\x1b[H{buf}\x1b[J")
+            .and_then(|_| stdout.flush())
+            .context("failed to write to stdout")?;
+        execute!(stdout, EndSynchronizedUpdate).context("failed to end synchronized update")?;
+        Ok(())
+    }
+
+    /// Formats the choices made in the previous steps.
+    fn summary(&self) -> Result<String> {
+        let mut parts = Vec::new();
+        for &step in &self.steps[..self.step] {
+            let part = match step {
+                Step::ColorMode => format!("mode &b{mode}&~", mode = self.color_mode.as_ref()),
+                Step::Theme => format!("theme &b{theme}&~", theme = self.theme.as_ref()),
+                Step::Preset => {
+                    format!("flag &b{preset}&~", preset = self.current_preset().as_ref())
+                },
+                Step::Lightness => format!(
+                    "brightness &b{lightness:.0}%&~",
+                    lightness = f32::from(self.current_lightness()) * 100.0
+                ),
+                Step::Arrangement | Step::Backend => continue,
+            };
+            parts.push(part);
+        }
+        color(parts.join("  "), self.color_mode).context("failed to color summary")
+    }
+
+    fn help(&self) -> &'static str {
+        match self.current_step() {
+            Step::Preset => {
+                "&8Type to search  ←↑↓→ move  Enter select  Backspace back  Ctrl-C quit&~"
+            },
+            Step::Lightness => "&8←→ ±1%  ↑↓ ±10%  Enter select  Backspace back  Ctrl-C quit&~",
+            Step::Arrangement => "&8↑↓ move  r reroll  Enter select  Backspace back  Ctrl-C quit&~",
+            _ => "&8↑↓ move  Enter select  Backspace back  Ctrl-C quit&~",
+        }
+    }
+
+    /// Recolors the logo with the current choices.
+    fn logo(&self) -> Result<Vec<String>> {
+        let color_profile = self
+            .color_profile(&self.current_preset())?
+            .with_lightness_adaptive(self.current_lightness(), self.theme);
+        let asc = self
+            .asc
+            .to_recolored(
+                &self.current_color_align(),
+                &color_profile,
+                LogoStyle::Foreground,
+                self.color_mode,
+                self.theme,
+            )
+            .context("failed to recolor ascii")?;
+        Ok(asc.lines)
+    }
+
+    fn panel(&mut self, panel_w: usize, panel_h: usize) -> Result<Vec<String>> {
+        let heading = |s: &str| color(format!("&a{s}&~"), self.color_mode);
+        let marker = |selected: bool| if selected { "> " } else { "  " };
+
+        let mut lines = Vec::new();
+        match self.current_step() {
+            Step::ColorMode => {
+                lines.push(heading("Which color system do you want to use?")?);
+                lines.push("Pick the one whose bar shows a smooth rainbow.".to_owned());
+                lines.push(String::new());
+                let rainbow = Preset::Rainbow.color_profile();
+                for mode in COLOR_MODES {
+                    let bar = rainbow
+                        .color_text(
+                            " ".repeat(BAR_WIDTH),
+                            mode,
+                            ForegroundBackground::Background,
+                            false,
+                        )
+                        .context("failed to color bar")?;
+                    lines.push(format!(
+                        "{marker}{name:<6}{bar}",
+                        marker = marker(mode == self.color_mode),
+                        name = mode.as_ref()
+                    ));
+                }
+            },
+            Step::Theme => {
+                lines.push(heading("Is your terminal in light mode or dark mode?")?);
+                lines.push(String::new());
+                for theme in THEMES {
+                    lines.push(format!(
+                        "{marker}{name}",
+                        marker = marker(theme == self.theme),
+                        name = theme.as_ref()
+                    ));
+                }
+            },
+            Step::Preset => {
+                lines.push(heading("Let's choose a flag!")?);
+                lines.push(format!(
+                    "Search: {search}_  ({n} of {total})",
+                    search = self.search,
+                    n = self.filtered.len(),
+                    total = self.presets.len()
+                ));
+                lines.push(String::new());
+                lines.extend(self.flag_grid(panel_w, panel_h.saturating_sub(lines.len()))?);
+            },
+            Step::Lightness => {
+                lines.push(heading("Let's adjust the color brightness!")?);
+                lines.push(format!(
+                    "The colors might be a little bit too {bright_dark} for {light_dark} mode.",
+                    bright_dark = match self.theme {
+                        TerminalTheme::Light => "bright",
+                        TerminalTheme::Dark => "dark",
+                    },
+                    light_dark = self.theme.as_ref()
+                ));
+                lines.push(String::new());
+                let lightness = f32::from(self.current_lightness());
+                let filled = (lightness * BAR_WIDTH as f32).round() as usize;
+                let filled = cmp::min(filled, BAR_WIDTH);
+                lines.push(format!(
+                    "[{filled}●{empty}] {percent:.0}%",
+                    filled = "━".repeat(filled),
+                    empty = "─".repeat(BAR_WIDTH.saturating_sub(filled)),
+                    percent = lightness * 100.0
+                ));
+                lines.push(format!(
+                    "Default: {default:.0}% for {light_dark} mode",
+                    default = f32::from(Config::default_lightness(self.theme)) * 100.0,
+                    light_dark = self.theme.as_ref()
+                ));
+            },
+            Step::Arrangement => {
+                lines.push(heading("Let's choose a color arrangement!")?);
+                lines.push(String::new());
+                for (i, (name, _)) in self.arrangements.iter().enumerate() {
+                    lines.push(format!(
+                        "{marker}{name}",
+                        marker = marker(i == self.arrangement_cursor)
+                    ));
+                }
+            },
+            Step::Backend => {
+                lines.push(heading("Select a *fetch backend")?);
+                lines.push(String::new());
+                for (i, (backend, description)) in self.backends.iter().enumerate() {
+                    lines.push(format!(
+                        "{marker}{name:<10}{description}",
+                        marker = marker(i == self.backend_cursor),
+                        name = backend.as_ref()
+                    ));
+                }
+            },
+        }
+
+        // Keep plain lines from wrapping
+        Ok(lines
+            .into_iter()
+            .map(|line| {
+                if line.contains('\x1b') {
+                    line
+                } else {
+                    line.chars().take(panel_w).collect()
+                }
+            })
+            .collect())
+    }
+
+    /// Lays out the presets matching the search in a grid, scrolled to keep
+    /// the cursor visible.
+    fn flag_grid(&mut self, panel_w: usize, grid_h: usize) -> Result<Vec<String>> {
+        let per_row = self.flags_per_row();
+        // Each row of flags takes a line for the names, one for the flags,
+        // and a blank line
+        let visible_rows = cmp::max(1, grid_h.div_euclid(3));
+        let cursor_row = self.preset_cursor.div_euclid(per_row);
+        if cursor_row < self.preset_scroll {
+            self.preset_scroll = cursor_row;
+        } else if cursor_row >= self.preset_scroll.saturating_add(visible_rows) {
+            self.preset_scroll = cursor_row.saturating_add(1).saturating_sub(visible_rows);
+        }
+
+        let mut lines = Vec::new();
+        for (row_i, row) in self
+            .filtered
+            .chunks(per_row)
+            .enumerate()
+            .skip(self.preset_scroll)
+            .take(visible_rows)
+        {
+            let mut names = Vec::new();
+            let mut flags = Vec::new();
+            for (col_i, &i) in row.iter().enumerate() {
+                let preset = &self.presets[i];
+                let selected =
+                    row_i.saturating_mul(per_row).saturating_add(col_i) == self.preset_cursor;
+                let name: String = preset.as_ref().chars().take(FLAG_WIDTH).collect();
+                let name = format!("{name:^FLAG_WIDTH$}");
+                names.push(if selected {
+                    format!("\x1b[7m{name}\x1b[27m")
+                } else {
+                    name
+                });
+                flags.push(
+                    self.color_profile(preset)?
+                        .color_text(
+                            " ".repeat(FLAG_WIDTH),
+                            self.color_mode,
+                            ForegroundBackground::Background,
+                            false,
+                        )
+                        .with_context(|| format!("failed to color flag of preset {preset:?}"))?,
+                );
+            }
+            lines.push(names.join("  "));
+            lines.push(flags.join("  "));
+            lines.push(String::new());
+        }
+        if self.filtered.is_empty() {
+            lines.push(
+                "No presets match the search"
+                    .chars()
+                    .take(panel_w)
+                    .collect(),
+            );
+        }
+        Ok(lines)
+    }
+}
+
+impl RawScreen {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode().context("failed to enable raw mode")?;
+        let screen = Self;
+        execute!(io::stdout(), EnterAlternateScreen, cursor::Hide)
+            .context("failed to enter alternate screen")?;
+        Ok(screen)
+    }
+}
+
+impl Drop for RawScreen {
+    fn drop(&mut self) {
+        // Nothing more can be done if restoring the terminal fails
+        let _ = execute!(io::stdout(), cursor::Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Moves to the previous or next item, wrapping around.
+fn cycle<T>(items: &[T], current: T, forward: bool) -> T
+where
+    T: Copy + PartialEq,
+{
+    let i = items.iter().position(|&item| item == current).unwrap_or(0);
+    let i = if forward {
+        i.checked_add(1).unwrap().checked_rem(items.len()).unwrap()
+    } else {
+        i.checked_add(items.len())
+            .unwrap()
+            .checked_sub(1)
+            .unwrap()
+            .checked_rem(items.len())
+            .unwrap()
+    };
+    items[i]
+}
+
+fn term_size() -> (usize, usize) {
+    terminal_size()
+        .map(|(Width(w), Height(h))| (w.into(), h.into()))
+        .unwrap_or((80, 24))
+}
+
+/// Lists the backends, each with a description and whether it is installed.
+fn backend_descriptions() -> Result<Vec<(Backend, String)>> {
+    let installed = |path: Option<String>| match path {
+        Some(path) => format!("(installed at {path})"),
+        None => "(not installed)".to_owned(),
+    };
+    Backend::VARIANTS
+        .iter()
+        .map(|name| {
+            let backend: Backend = name.parse().expect("backend name should be valid");
+            let description = match backend {
+                Backend::Neofetch => "bash, best compatibility on Unix systems".to_owned(),
+                Backend::Fastfetch => format!(
+                    "C, best performance {installed}",
+                    installed =
+                        installed(fastfetch_path().ok().map(|path| path.display().to_string()))
+                ),
+                #[cfg(feature = "macchina")]
+                Backend::Macchina => format!(
+                    "Rust, best performance {installed}",
+                    installed = installed(
+                        macchina_path()
+                            .context("failed to get macchina path")?
+                            .map(|path| path.display().to_string())
+                    )
+                ),
+                Backend::Native => "built in, no dependencies, fewer details".to_owned(),
+            };
+            Ok((backend, description))
+        })
+        .collect()
+}
+
+/// Reads a key press from a terminal in raw mode.
+///
+/// Resizing the terminal is reported as [`Key::Other`], so that the wizard is
+/// redrawn.
+fn read_key() -> io::Result<Key> {
+    loop {
+        if let Some(key) = to_key(event::read()?) {
+            return Ok(key);
+        }
+    }
+}
+
+/// Maps a terminal event to a key press, ignoring key releases and events
+/// that don't need a redraw.
+fn to_key(event: Event) -> Option<Key> {
+    let KeyEvent {
+        code,
+        modifiers,
+        kind,
+        ..
+    } = match event {
+        Event::Key(key_event) => key_event,
+        Event::Resize(..) => return Some(Key::Other),
+        _ => return None,
+    };
+    if kind == KeyEventKind::Release {
+        return None;
+    }
+    let key = match code {
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Char('c' | 'd') if modifiers.contains(KeyModifiers::CONTROL) => Key::Cancel,
+        KeyCode::Char(c) if !modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+            Key::Char(c)
+        },
+        _ => Key::Other,
+    };
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_events() {
+        let press = |code, modifiers| Event::Key(KeyEvent::new(code, modifiers));
+        let keys: Vec<Option<Key>> = [
+            press(KeyCode::Up, KeyModifiers::NONE),
+            press(KeyCode::Left, KeyModifiers::SHIFT),
+            press(KeyCode::Char('q'), KeyModifiers::NONE),
+            press(KeyCode::Enter, KeyModifiers::NONE),
+            press(KeyCode::Backspace, KeyModifiers::NONE),
+            press(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            press(KeyCode::Char('é'), KeyModifiers::NONE),
+            Event::Resize(80, 24),
+            Event::FocusGained,
+        ]
+        .into_iter()
+        .map(to_key)
+        .collect();
+        assert_eq!(
+            keys,
+            [
+                Some(Key::Up),
+                Some(Key::Left),
+                Some(Key::Char('q')),
+                Some(Key::Enter),
+                Some(Key::Backspace),
+                Some(Key::Cancel),
+                Some(Key::Char('é')),
+                Some(Key::Other),
+                None,
+            ]
+        );
+
+        let release = KeyEvent::new_with_kind(
+            KeyCode::Char('q'),
+            KeyModifiers::NONE,
+            KeyEventKind::Release,
+        );
+        assert_eq!(to_key(Event::Key(release)), None);
+    }
+
+    #[test]
+    fn lone_escape_keeps_next_key() {
+        // A lone Esc is its own event, so the key after it is not lost
+        let keys: Vec<Option<Key>> = [KeyCode::Esc, KeyCode::Char('q')]
+            .into_iter()
+            .map(|code| to_key(Event::Key(KeyEvent::new(code, KeyModifiers::NONE))))
+            .collect();
+        assert_eq!(keys, [Some(Key::Other), Some(Key::Char('q'))]);
+    }
+}
@@ -5,10 +5,8 @@ use strum::{AsRefStr, EnumString, VariantNames};
 #[serde(rename_all = "lowercase")]
 #[strum(serialize_all = "lowercase")]
 pub enum AnsiMode {
-    #[serde(rename = "ansi")]
-    #[serde(skip)]
-    #[strum(serialize = "ansi")]
-    #[strum(disabled)]
+    #[serde(rename = "ansi", alias = "16")]
+    #[strum(to_string = "16", serialize = "ansi")]
     Ansi16,
     #[serde(rename = "8bit")]
     #[strum(serialize = "8bit")]
@@ -56,9 +54,135 @@ pub enum Backend {
     Fastfetch,
     #[cfg(feature = "macchina")]
     Macchina,
+    Native,
+}
+
+#[derive(
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Hash,
+    Debug,
+    AsRefStr,
+    Deserialize,
+    EnumString,
+    Serialize,
+    VariantNames,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum OutputFormat {
+    Svg,
+    Html,
+}
+
+/// Output format of the `list` subcommands.
+#[derive(
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Hash,
+    Debug,
+    Default,
+    AsRefStr,
+    Deserialize,
+    EnumString,
+    Serialize,
+    VariantNames,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum ListFormat {
+    /// Human-readable, with colored swatches
+    #[default]
+    Text,
+    Json,
+}
+
+/// How colors of a profile are blended when spread across lines or columns.
+#[derive(
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Hash,
+    Debug,
+    Default,
+    AsRefStr,
+    Deserialize,
+    EnumString,
+    Serialize,
+    VariantNames,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Blend {
+    /// Discrete stripes of each color
+    #[default]
+    Stripes,
+    /// Smooth gradient interpolated in linear sRGB
+    Linear,
+    /// Smooth gradient interpolated in Oklab
+    Oklab,
+}
+
+#[derive(
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Hash,
+    Debug,
+    Default,
+    AsRefStr,
+    Deserialize,
+    EnumString,
+    Serialize,
+    VariantNames,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum LogoStyle {
+    /// Color the characters of the ascii art
+    #[default]
+    Foreground,
+    /// Paint every cell with background colors, like a block flag, and draw
+    /// the characters in a contrasting gray
+    Background,
+    /// Color the characters, and paint the spaces with background colors
+    Both,
+}
+
+/// Characters used to draw an image as ascii art.
+#[derive(
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Hash,
+    Debug,
+    Default,
+    AsRefStr,
+    Deserialize,
+    EnumString,
+    Serialize,
+    VariantNames,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum ImageCharset {
+    /// Braille patterns, with 2x4 dots per cell
+    Braille,
+    /// Half blocks, with 1x2 dots per cell
+    #[default]
+    HalfBlock,
+    /// Characters of increasing density, picked by the lightness of each cell
+    Ramp,
 }
 
 // See https://github.com/Peternator7/strum/issues/244
 impl VariantNames for AnsiMode {
-    const VARIANTS: &'static [&'static str] = &["8bit", "rgb"];
+    const VARIANTS: &'static [&'static str] = &["16", "8bit", "rgb"];
 }